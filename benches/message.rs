@@ -0,0 +1,143 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use dobot_fx24::{
+    base::CommandID,
+    error::{Error as DobotError, Result as DobotResult},
+    message::{DobotMessage, DobotMessageRef, MessageDecoder, ParamsWriter, MAX_FRAME_LEN},
+};
+use std::io::Read;
+
+/// A CP command as streamed while drawing.
+fn cp_request() -> DobotMessage {
+    let params = ParamsWriter::new()
+        .u8(0x01)
+        .f32s(&[200.0, 50.0, 0.0])
+        .u8(0x00)
+        .finish();
+    DobotMessage::new(CommandID::SetCpCmd, true, true, params).unwrap()
+}
+
+/// The allocating encoder `to_bytes` used before `encode_into` existed.
+fn chained_to_bytes(msg: &DobotMessage) -> Vec<u8> {
+    let ctrl = ((msg.is_queued() as u8) << 1) | (msg.rw() as u8);
+    msg.header()
+        .iter()
+        .chain([msg.len()].iter())
+        .chain([u8::from(msg.id())].iter())
+        .chain([ctrl].iter())
+        .chain(msg.params().iter())
+        .chain([msg.checksum()].iter())
+        .copied()
+        .collect::<Vec<u8>>()
+}
+
+/// The allocating decoder `from_bytes` used before `DobotMessageRef` existed.
+fn collected_from_bytes(bytes: &[u8]) -> DobotResult<DobotMessage> {
+    if bytes.len() < 6 || bytes.len() != bytes[2] as usize + 4 {
+        return Err(DobotError::DeserializeError("message is truncated".into()));
+    }
+    let id = CommandID::from(bytes[3]);
+    let ctrl = bytes[4];
+    let params = bytes[5..(bytes.len() - 1)].to_vec();
+    let checksum = bytes[bytes.len() - 1];
+    let msg = DobotMessage::new(id, (ctrl & 0x01) != 0, (ctrl & 0x02) != 0, params)?;
+    if msg.checksum() != checksum {
+        return Err(DobotError::IntegrityError {
+            expected: msg.checksum(),
+            received: checksum,
+        });
+    }
+    Ok(msg)
+}
+
+/// The allocating reader `from_async_reader` used before `MessageDecoder` existed,
+/// reading synchronously so the benchmark needs no runtime.
+fn concatenated_from_reader<R>(mut reader: R) -> DobotResult<DobotMessage>
+where
+    R: Read,
+{
+    let mut prefix = [0u8; 5];
+    reader.read_exact(&mut prefix)?;
+    let mut suffix = vec![0u8; prefix[2] as usize - 1];
+    reader.read_exact(suffix.as_mut_slice())?;
+    let bytes = [prefix.as_slice(), suffix.as_slice()].concat();
+    collected_from_bytes(&bytes)
+}
+
+fn encode(c: &mut Criterion) {
+    let msg = cp_request();
+    assert_eq!(chained_to_bytes(&msg), msg.to_bytes());
+    let mut group = c.benchmark_group("encode");
+    group.bench_function("chained_to_bytes", |b| {
+        b.iter(|| chained_to_bytes(black_box(&msg)))
+    });
+    group.bench_function("to_bytes", |b| b.iter(|| black_box(&msg).to_bytes()));
+    group.bench_function("encode_into", |b| {
+        let mut buffer = [0u8; MAX_FRAME_LEN];
+        b.iter(|| black_box(&msg).encode_into(&mut buffer).unwrap())
+    });
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let bytes = cp_request().to_bytes();
+    let mut group = c.benchmark_group("decode");
+    group.bench_function("collected_from_bytes", |b| {
+        b.iter(|| collected_from_bytes(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("DobotMessage::from_bytes", |b| {
+        b.iter(|| DobotMessage::from_bytes(black_box(&bytes)).unwrap())
+    });
+    group.bench_function("DobotMessageRef::from_bytes", |b| {
+        b.iter(|| {
+            DobotMessageRef::from_bytes(black_box(&bytes))
+                .unwrap()
+                .checksum()
+        })
+    });
+    group.finish();
+}
+
+fn stream(c: &mut Criterion) {
+    let stream = (0..64)
+        .flat_map(|_| cp_request().to_bytes())
+        .collect::<Vec<u8>>();
+    let mut group = c.benchmark_group("stream");
+    group.bench_function("concatenated_from_reader", |b| {
+        b.iter(|| {
+            let mut reader = black_box(stream.as_slice());
+            for _ in 0..64 {
+                concatenated_from_reader(&mut reader).unwrap();
+            }
+        })
+    });
+    group.bench_function("from_reader", |b| {
+        b.iter(|| {
+            let mut reader = black_box(stream.as_slice());
+            for _ in 0..64 {
+                DobotMessage::from_reader(&mut reader).unwrap();
+            }
+        })
+    });
+    group.bench_function("decode", |b| {
+        let mut decoder = MessageDecoder::new();
+        b.iter(|| {
+            decoder.extend(black_box(&stream));
+            while let Some(result) = decoder.decode() {
+                result.unwrap();
+            }
+        })
+    });
+    group.bench_function("decode_ref", |b| {
+        let mut decoder = MessageDecoder::new();
+        b.iter(|| {
+            decoder.extend(black_box(&stream));
+            while let Some(result) = decoder.decode_ref() {
+                result.unwrap();
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, encode, decode, stream);
+criterion_main!(benches);
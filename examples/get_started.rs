@@ -1,12 +1,11 @@
-use failure::Fallible;
-use dobot_24::Dobot;
+use dobot_fx24::{error::Result as DobotResult, Dobot};
 
 #[tokio::main]
-async fn main() -> Fallible<()> {
+async fn main() -> DobotResult<()> {
     // Change the path to your corresponding Dobot port.
     let mut dobot = Dobot::open("/dev/ttyUSB0").await?;
 
-    print_pose(&mut dobot).await; 
+    print_pose(&mut dobot).await;
     dobot.set_home().await?.wait().await?;
     print_pose(&mut dobot).await;
 
@@ -18,7 +17,7 @@ async fn main() -> Fallible<()> {
     Ok(())
 }
 
-async fn print_pose<'a>(internal_dobot: &'a mut Dobot) {
+async fn print_pose(internal_dobot: &mut Dobot) {
     let pose = internal_dobot.get_pose().await.unwrap();
     println!(
         "Pose: x: {} y: {} z: {} r: {} j1: {} j2: {} j3: {} j4: {}",
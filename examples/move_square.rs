@@ -1,8 +1,7 @@
-use dobot::Dobot;
-use failure::Fallible;
+use dobot_fx24::{error::Result as DobotResult, Dobot};
 
 #[tokio::main]
-async fn main() -> Fallible<()> {
+async fn main() -> DobotResult<()> {
     let mut dobot = Dobot::open("/dev/ttyUSB0").await?;
 
     println!("pose {:#?}", dobot.get_pose().await?);
@@ -0,0 +1,132 @@
+//! Alarms reported by the controller.
+use std::fmt;
+
+/// Names of the alarm codes documented for the controller firmware.
+const ALARM_NAMES: &[(u8, &str)] = &[
+    (0x00, "controller reset"),
+    (0x01, "undefined instruction"),
+    (0x02, "file system error"),
+    (0x03, "MCU and FPGA communication failure"),
+    (0x04, "angle sensor reading error"),
+    (0x10, "planned pose is a singularity"),
+    (0x11, "planned pose has no inverse kinematics solution"),
+    (0x12, "planned pose exceeds the joint limits"),
+    (0x13, "repeated data pushed to the planner"),
+    (0x14, "invalid arc parameters"),
+    (0x15, "invalid jump parameters"),
+    (0x20, "motion passes a singularity"),
+    (0x21, "motion has no inverse kinematics solution"),
+    (0x22, "motion exceeds the joint limits"),
+    (0x30, "joint 1 overspeed"),
+    (0x31, "joint 2 overspeed"),
+    (0x32, "joint 3 overspeed"),
+    (0x33, "joint 4 overspeed"),
+    (0x40, "joint 1 positive limit"),
+    (0x41, "joint 1 negative limit"),
+    (0x42, "joint 2 positive limit"),
+    (0x43, "joint 2 negative limit"),
+    (0x44, "joint 3 positive limit"),
+    (0x45, "joint 3 negative limit"),
+    (0x46, "joint 4 positive limit"),
+    (0x47, "joint 4 negative limit"),
+    (0x48, "joints 2 and 3 positive limit"),
+    (0x49, "joints 2 and 3 negative limit"),
+    (0x50, "joint 1 lost steps"),
+    (0x51, "joint 2 lost steps"),
+    (0x52, "joint 3 lost steps"),
+    (0x53, "joint 4 lost steps"),
+];
+
+/// Alarm codes raised when a target cannot be planned or reached.
+const WORKSPACE_ALARMS: &[u8] = &[0x10, 0x11, 0x12, 0x20, 0x21, 0x22];
+
+/// Get a description of the alarm code, if it is a known one.
+pub fn describe_alarm(code: u8) -> Option<&'static str> {
+    ALARM_NAMES
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, name)| *name)
+}
+
+/// The set of active alarms, one bit per alarm code.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Alarms {
+    bits: Vec<u8>,
+}
+
+impl Alarms {
+    /// Create alarm set from the bytes of a GetAlarmsState response.
+    pub fn from_bytes<B>(bytes: B) -> Self
+    where
+        B: AsRef<[u8]>,
+    {
+        Self {
+            bits: bytes.as_ref().to_vec(),
+        }
+    }
+
+    /// Get the raw alarm bits.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bits
+    }
+
+    /// Check whether no alarm is active.
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|byte| *byte == 0)
+    }
+
+    /// Check whether the alarm with the given code is active.
+    pub fn contains(&self, code: u8) -> bool {
+        self.bits
+            .get(code as usize / 8)
+            .is_some_and(|byte| byte & (1 << (code % 8)) != 0)
+    }
+
+    /// Check whether an active alarm says a target is out of the workspace.
+    pub fn is_out_of_workspace(&self) -> bool {
+        WORKSPACE_ALARMS.iter().any(|code| self.contains(*code))
+    }
+
+    /// Get the codes of all active alarms in ascending order.
+    pub fn codes(&self) -> Vec<u8> {
+        (0..(self.bits.len() * 8).min(256))
+            .map(|code| code as u8)
+            .filter(|code| self.contains(*code))
+            .collect()
+    }
+}
+
+impl fmt::Display for Alarms {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let codes = self.codes();
+        if codes.is_empty() {
+            return write!(f, "no alarms");
+        }
+        for (nth, code) in codes.into_iter().enumerate() {
+            if nth > 0 {
+                write!(f, ", ")?;
+            }
+            match describe_alarm(code) {
+                Some(name) => write!(f, "{:#04x} ({})", code, name)?,
+                None => write!(f, "{:#04x}", code)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decides how the controller is recovered when a wait runs into an alarm.
+///
+/// The wait fails with [Error::OutOfWorkspace](crate::error::Error::OutOfWorkspace)
+/// if the alarms say the target is unreachable, and with
+/// [Error::Alarm](crate::error::Error::Alarm) otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlarmPolicy {
+    /// Leave the alarms and the queue as they are.
+    #[default]
+    Report,
+    /// Clear the alarms so the queue resumes with the next command.
+    ClearAlarms,
+    /// Clear the alarms and drop all queued commands.
+    ClearAlarmsAndQueue,
+}
@@ -1,14 +1,31 @@
 use crate::{
+    alarm::{AlarmPolicy, Alarms},
+    command::{
+        ClearAllAlarmsState, Command, GetAlarmsState, GetDeviceName, GetDeviceSn, GetPose,
+        GetQueuedCmdCurrentIndex, GetQueuedCmdLeftSpace, Response, SetCpCmd, SetEndEffectorGripper,
+        SetEndEffectorSuctionCup, SetHomeCmd, SetPtpCmd, SetPtpCommonParams,
+        SetPtpCoordinateParams, SetPtpJointParams, SetPtpJumpParams, SetQueuedCmdClear,
+        SetQueuedCmdStartExec, SetQueuedCmdStopExec,
+    },
     error::{Error as DobotError, Result as DobotResult},
-    message::DobotMessage,
+    message::{DobotMessage, MessageDecoder, HEADER, MAX_FRAME_LEN},
+    retry::RetryPolicy,
+    rt,
+    transport::{self, Transport},
+    wait::{Progress, WaitOptions},
 };
-use num_derive::FromPrimitive;
-use std::{convert::TryInto, path::Path, time::Duration};
-use tokio::io::AsyncWriteExt;
-use tokio_serial::{DataBits, FlowControl, Parity, SerialStream, SerialPortBuilderExt, StopBits};
+use futures_util::io::{AsyncReadExt, AsyncWriteExt};
+use std::{
+    collections::VecDeque,
+    io::{Error as StdIoError, ErrorKind},
+    path::Path,
+    time::Duration,
+};
+
+pub use crate::message::CommandID;
 
 /// Defines the format to describe the robot pose.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     #[allow(non_camel_case_types)]
     MODE_PTP_JUMP_XYZ = 0x00,
@@ -32,39 +49,118 @@ pub enum Mode {
     MODE_PTP_JUMP_MOVL_XYZ = 0x09,
 }
 
-/// Describes the pose of robot arm.
-#[derive(Debug, Clone, PartialEq)]
-pub struct Pose {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
-    pub r: f32,
-    pub j1: f32,
-    pub j2: f32,
-    pub j3: f32,
-    pub j4: f32,
+/// Selects how the arm travels to a [`Target`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Motion {
+    /// Lifts to the jump height, travels, then lowers onto the target.
+    Jump,
+    /// Interpolates in joint space. The tool path is not a straight line.
+    Joint,
+    /// Moves the tool along a straight line in Cartesian space.
+    Linear,
+}
+
+/// Describes the destination of a point-to-point move.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    /// Absolute Cartesian coordinates in millimeters and end effector rotation in degrees.
+    Cartesian { x: f32, y: f32, z: f32, r: f32 },
+    /// Absolute joint angles in degrees.
+    Joints { j1: f32, j2: f32, j3: f32, j4: f32 },
+    /// Cartesian offsets from the current pose.
+    RelativeCartesian { x: f32, y: f32, z: f32, r: f32 },
+    /// Joint angle offsets from the current pose.
+    RelativeJoints { j1: f32, j2: f32, j3: f32, j4: f32 },
+}
+
+impl Target {
+    /// Get the PTP mode that moves to this target with the given motion.
+    ///
+    /// Returns `None` if the firmware has no such mode, e.g. jumping to a relative target.
+    pub fn mode(&self, motion: Motion) -> Option<Mode> {
+        let mode = match (self, motion) {
+            (Target::Cartesian { .. }, Motion::Jump) => Mode::MODE_PTP_JUMP_XYZ,
+            (Target::Cartesian { .. }, Motion::Joint) => Mode::MODE_PTP_MOVJ_XYZ,
+            (Target::Cartesian { .. }, Motion::Linear) => Mode::MODE_PTP_MOVL_XYZ,
+            (Target::Joints { .. }, Motion::Jump) => Mode::MODE_PTP_JUMP_ANGLE,
+            (Target::Joints { .. }, Motion::Joint) => Mode::MODE_PTP_MOVJ_ANGLE,
+            (Target::Joints { .. }, Motion::Linear) => Mode::MODE_PTP_MOVL_ANGLE,
+            (Target::RelativeCartesian { .. }, Motion::Joint) => Mode::MODE_PTP_MOVJ_XYZ_INC,
+            (Target::RelativeCartesian { .. }, Motion::Linear) => Mode::MODE_PTP_MOVL_INC,
+            (Target::RelativeJoints { .. }, Motion::Joint) => Mode::MODE_PTP_MOVJ_INC,
+            _ => return None,
+        };
+        Some(mode)
+    }
+
+    /// Get the four values sent as PTP command parameters.
+    pub fn values(&self) -> [f32; 4] {
+        match *self {
+            Target::Cartesian { x, y, z, r } | Target::RelativeCartesian { x, y, z, r } => {
+                [x, y, z, r]
+            }
+            Target::Joints { j1, j2, j3, j4 } | Target::RelativeJoints { j1, j2, j3, j4 } => {
+                [j1, j2, j3, j4]
+            }
+        }
+    }
+}
+
+crate::protocol_params! {
+    /// Describes the pose of robot arm.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Pose {
+        pub x: f32,
+        pub y: f32,
+        pub z: f32,
+        pub r: f32,
+        pub j1: f32,
+        pub j2: f32,
+        pub j3: f32,
+        pub j4: f32,
+    }
 }
 
+/// The default time to wait for a response.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// The Dobot robot arm controller type.
 pub struct Dobot {
-    serial: SerialStream,
+    transport: Box<dyn Transport>,
+    decoder: MessageDecoder,
+    outgoing: Vec<u8>,
+    partial: usize,
+    in_flight: VecDeque<(CommandID, bool)>,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    wait_options: WaitOptions,
+    last_queued_index: Option<u64>,
+    queue_epoch: u64,
 }
 
 impl Dobot {
     /// Create controller object from device file.
+    #[cfg(any(feature = "tokio", all(feature = "async-io", unix)))]
     pub async fn open<P>(path: P) -> DobotResult<Self>
     where
         P: AsRef<Path>,
     {
-        let internal_path = path.as_ref().to_str().ok_or(DobotError::DeserializeError("path failed to be parsed".to_string()))?;
-        let serial = tokio_serial::new(internal_path, 115200)
-            .data_bits(DataBits::Eight)
-            .flow_control(FlowControl::None)
-            .parity(Parity::None)
-            .stop_bits(StopBits::One)
-            .timeout(Duration::from_secs(1)).open_native_async()?;
+        let internal_path = path.as_ref().to_str().ok_or(DobotError::DeserializeError(
+            "path failed to be parsed".to_string(),
+        ))?;
+        let serial = transport::open_serial(internal_path)?;
+
+        Self::from_transport(serial).await
+    }
 
-        let mut dobot = Self { serial };
+    /// Create controller object over an arbitrary transport.
+    ///
+    /// The controller is initialized the same way as in [Dobot::open].
+    pub async fn from_transport<T>(transport: T) -> DobotResult<Self>
+    where
+        T: Transport + 'static,
+    {
+        let mut dobot = Self::uninitialized(transport);
 
         dobot.set_queued_cmd_start_exec().await?;
         dobot.set_queued_cmd_clear().await?;
@@ -79,6 +175,60 @@ impl Dobot {
         Ok(dobot)
     }
 
+    /// Find the controllers connected to serial ports.
+    ///
+    /// See [crate::discovery] for how ports are chosen and probed.
+    #[cfg(any(feature = "tokio", all(feature = "async-io", unix)))]
+    pub async fn discover() -> DobotResult<Vec<crate::discovery::DiscoveredDobot>> {
+        crate::discovery::discover().await
+    }
+
+    /// Open the controller with the given serial number.
+    #[cfg(any(feature = "tokio", all(feature = "async-io", unix)))]
+    pub async fn open_by_serial(serial: &str) -> DobotResult<Self> {
+        let found = Self::discover().await?;
+        match found.into_iter().find(|dobot| dobot.serial == serial) {
+            Some(dobot) => Self::open(&dobot.path).await,
+            None => Err(DobotError::DeviceNotFound(format!(
+                "no controller with serial number {}",
+                serial
+            ))),
+        }
+    }
+
+    /// Open the controller with the given name.
+    #[cfg(any(feature = "tokio", all(feature = "async-io", unix)))]
+    pub async fn open_by_name(name: &str) -> DobotResult<Self> {
+        let found = Self::discover().await?;
+        match found.into_iter().find(|dobot| dobot.name == name) {
+            Some(dobot) => Self::open(&dobot.path).await,
+            None => Err(DobotError::DeviceNotFound(format!(
+                "no controller named {}",
+                name
+            ))),
+        }
+    }
+
+    /// Create controller object over a transport without initializing the controller.
+    pub(crate) fn uninitialized<T>(transport: T) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self {
+            transport: Box::new(transport),
+            decoder: MessageDecoder::new(),
+            outgoing: Vec::new(),
+            partial: 0,
+            in_flight: VecDeque::new(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            retry_policy: RetryPolicy::default(),
+            wait_options: WaitOptions::default(),
+            last_queued_index: None,
+            queue_epoch: 0,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub async fn set_ptp_joint_params<'a>(
         &'a mut self,
         v_x: f32,
@@ -90,33 +240,12 @@ impl Dobot {
         a_z: f32,
         a_r: f32,
     ) -> DobotResult<WaitHandle<'a>> {
-        let params = [
-            v_x.to_le_bytes(),
-            v_y.to_le_bytes(),
-            v_z.to_le_bytes(),
-            v_r.to_le_bytes(),
-            a_x.to_le_bytes(),
-            a_y.to_le_bytes(),
-            a_z.to_le_bytes(),
-            a_r.to_le_bytes(),
-        ]
-        .iter()
-        .flatten()
-        .map(|byte| *byte)
-        .collect::<Vec<u8>>();
-
-        let response_msg = self
-            .send_command(DobotMessage::new(
-                CommandID::GetSetPtpJointParams,
-                true,
-                true,
-                params,
-            )?)
-            .await?;
-        let index = u64::from_le_bytes(response_msg.params()[0..8].try_into().unwrap());
-
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let command = SetPtpJointParams {
+            velocity: [v_x, v_y, v_z, v_r],
+            acceleration: [a_x, a_y, a_z, a_r],
+        };
+        let index = self.execute(&command).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     pub async fn set_cp_cmd<'a>(
@@ -125,24 +254,8 @@ impl Dobot {
         y: f32,
         z: f32,
     ) -> DobotResult<WaitHandle<'a>> {
-        let params = [0x01]
-            .iter()
-            .chain(
-                [x.to_le_bytes(), y.to_le_bytes(), z.to_le_bytes()]
-                    .iter()
-                    .flatten(),
-            )
-            .chain([0x00].iter())
-            .map(|byte| *byte)
-            .collect::<Vec<u8>>();
-
-        let response_msg = self
-            .send_command(DobotMessage::new(CommandID::SetCpCmd, true, true, params)?)
-            .await?;
-        let index = u64::from_le_bytes(response_msg.params()[0..8].try_into().unwrap());
-
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let index = self.execute(&SetCpCmd { x, y, z }).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     pub async fn set_ptp_coordinate_params<'a>(
@@ -150,29 +263,14 @@ impl Dobot {
         velocity: f32,
         acceleration: f32,
     ) -> DobotResult<WaitHandle<'a>> {
-        let params = [
-            velocity.to_le_bytes(),
-            velocity.to_le_bytes(),
-            acceleration.to_le_bytes(),
-            acceleration.to_le_bytes(),
-        ]
-        .iter()
-        .flatten()
-        .map(|byte| *byte)
-        .collect::<Vec<u8>>();
-
-        let response_msg = self
-            .send_command(DobotMessage::new(
-                CommandID::GetSetPtpCoordinateParams,
-                true,
-                true,
-                params,
-            )?)
-            .await?;
-        let index = u64::from_le_bytes(response_msg.params()[0..8].try_into().unwrap());
-
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let command = SetPtpCoordinateParams {
+            xyz_velocity: velocity,
+            r_velocity: velocity,
+            xyz_acceleration: acceleration,
+            r_acceleration: acceleration,
+        };
+        let index = self.execute(&command).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     pub async fn set_ptp_jump_params<'a>(
@@ -180,24 +278,12 @@ impl Dobot {
         jump: f32,
         limit: f32,
     ) -> DobotResult<WaitHandle<'a>> {
-        let params = [jump.to_le_bytes(), limit.to_le_bytes()]
-            .iter()
-            .flatten()
-            .map(|byte| *byte)
-            .collect::<Vec<u8>>();
-
-        let response_msg = self
-            .send_command(DobotMessage::new(
-                CommandID::GetSetPtpJumpParams,
-                true,
-                true,
-                params,
-            )?)
-            .await?;
-        let index = u64::from_le_bytes(response_msg.params()[0..8].try_into().unwrap());
-
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let command = SetPtpJumpParams {
+            jump_height: jump,
+            z_limit: limit,
+        };
+        let index = self.execute(&command).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     pub async fn set_ptp_common_params<'a>(
@@ -205,24 +291,12 @@ impl Dobot {
         velocity: f32,
         acceleration: f32,
     ) -> DobotResult<WaitHandle<'a>> {
-        let params = [velocity.to_le_bytes(), acceleration.to_le_bytes()]
-            .iter()
-            .flatten()
-            .map(|byte| *byte)
-            .collect::<Vec<u8>>();
-
-        let response_msg = self
-            .send_command(DobotMessage::new(
-                CommandID::GetSetPtpCommonParams,
-                true,
-                true,
-                params,
-            )?)
-            .await?;
-        let index = u64::from_le_bytes(response_msg.params()[0..8].try_into().unwrap());
-
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let command = SetPtpCommonParams {
+            velocity_ratio: velocity,
+            acceleration_ratio: acceleration,
+        };
+        let index = self.execute(&command).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     pub async fn set_ptp_cmd<'a>(
@@ -233,109 +307,65 @@ impl Dobot {
         r: f32,
         mode: Mode,
     ) -> DobotResult<WaitHandle<'a>> {
-        let request_msg = {
-            let params = [mode as u8]
-                .iter()
-                .chain(
-                    [
-                        x.to_le_bytes(),
-                        y.to_le_bytes(),
-                        z.to_le_bytes(),
-                        r.to_le_bytes(),
-                    ]
-                    .iter()
-                    .flatten(),
-                )
-                .map(|byte| *byte)
-                .collect::<Vec<u8>>();
-            DobotMessage::new(CommandID::SetPtpCmd, true, true, params)?
-        };
-
-        let response_msg = self.send_command(request_msg).await?;
-        let params = response_msg.params();
-        let index = u64::from_le_bytes(params[0..8].try_into().unwrap());
-
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let index = self.execute(&SetPtpCmd { mode, x, y, z, r }).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     pub async fn set_end_effector_suction_cup<'a>(
         &'a mut self,
         enable: bool,
     ) -> DobotResult<WaitHandle<'a>> {
-        let params = vec![0x01, enable as u8];
-        let response_msg = self
-            .send_command(DobotMessage::new(
-                CommandID::GetSetEndEffectorSuctionCup,
-                true,
-                true,
-                params,
-            )?)
-            .await?;
-        let index = u64::from_le_bytes(response_msg.params()[0..8].try_into().unwrap());
-
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let index = self.execute(&SetEndEffectorSuctionCup { enable }).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     pub async fn set_end_effector_gripper<'a>(
         &'a mut self,
         enable: bool,
     ) -> DobotResult<WaitHandle<'a>> {
-        let params = vec![0x01, enable as u8];
-        let response_msg = self
-            .send_command(DobotMessage::new(
-                CommandID::GetSetEndEffectorGripper,
-                true,
-                true,
-                params,
-            )?)
-            .await?;
-        let index = u64::from_le_bytes(response_msg.params()[0..8].try_into().unwrap());
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let index = self.execute(&SetEndEffectorGripper { enable }).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     pub async fn set_queued_cmd_start_exec(&mut self) -> DobotResult<()> {
-        self.send_command(DobotMessage::new(
-            CommandID::SetQueuedCmdStartExec,
-            true,
-            false,
-            vec![],
-        )?)
-        .await?;
-        Ok(())
+        self.execute(&SetQueuedCmdStartExec).await
     }
 
     pub async fn set_queued_cmd_stop_exec(&mut self) -> DobotResult<()> {
-        self.send_command(DobotMessage::new(
-            CommandID::SetQueuedCmdStopExec,
-            true,
-            false,
-            vec![],
-        )?)
-        .await?;
-        Ok(())
+        self.execute(&SetQueuedCmdStopExec).await
     }
 
     pub async fn set_queued_cmd_clear(&mut self) -> DobotResult<()> {
-        self.send_command(DobotMessage::new(
-            CommandID::SetQueuedCmdClear,
-            true,
-            false,
-            vec![],
-        )?)
-        .await?;
-        Ok(())
+        self.execute(&SetQueuedCmdClear).await
+    }
+
+    /// Get the serial number of the device.
+    pub async fn get_device_sn(&mut self) -> DobotResult<String> {
+        self.execute(&GetDeviceSn).await
+    }
+
+    /// Get the name of the device.
+    pub async fn get_device_name(&mut self) -> DobotResult<String> {
+        self.execute(&GetDeviceName).await
+    }
+
+    /// Get the number of commands the firmware queue can still take.
+    pub async fn get_queued_cmd_left_space(&mut self) -> DobotResult<u32> {
+        self.execute(&GetQueuedCmdLeftSpace).await
+    }
+
+    /// Get the alarms raised by the controller.
+    pub async fn get_alarms_state(&mut self) -> DobotResult<Alarms> {
+        self.execute(&GetAlarmsState).await
+    }
+
+    /// Clear all alarms, which lets the queue continue.
+    pub async fn clear_all_alarms_state(&mut self) -> DobotResult<()> {
+        self.execute(&ClearAllAlarmsState).await
     }
 
     pub async fn get_queued_cmd_current_index(&mut self) -> DobotResult<u64> {
-        let request_msg =
-            DobotMessage::new(CommandID::SetQueuedCmdCurrentIndex, false, false, vec![])?;
-        let response_msg = self.send_command(request_msg).await?;
-        let params = response_msg.params();
-        let index = u64::from_le_bytes(params[0..8].try_into().unwrap());
-        Ok(index)
+        self.execute(&GetQueuedCmdCurrentIndex).await
     }
 
     /// Grips on end effector.
@@ -352,48 +382,13 @@ impl Dobot {
 
     /// Starts the calibration process.
     pub async fn set_home<'a>(&'a mut self) -> DobotResult<WaitHandle<'a>> {
-        let request_msg = DobotMessage::new(CommandID::SetHomeCmd, true, true, vec![])?;
-        let response_msg = self.send_command(request_msg).await?;
-        let params = response_msg.params();
-        let index = u64::from_le_bytes(params[0..8].try_into().unwrap());
-        let handle = WaitHandle::new(self, index);
-        Ok(handle)
+        let index = self.execute(&SetHomeCmd).await?;
+        Ok(WaitHandle::new(self, index))
     }
 
     /// Get the current pose of robot.
     pub async fn get_pose(&mut self) -> DobotResult<Pose> {
-        let request_msg = DobotMessage::new(CommandID::GetPose, false, false, vec![])?;
-        let response_msg = self.send_command(request_msg).await?;
-
-        let params = {
-            let params = response_msg.params();
-            if params.len() != 32 {
-                return Err(DobotError::DeserializeError("message is truncated".into()));
-            }
-            params
-        };
-
-        let x = f32::from_le_bytes(params[0..4].try_into().unwrap());
-        let y = f32::from_le_bytes(params[4..8].try_into().unwrap());
-        let z = f32::from_le_bytes(params[8..12].try_into().unwrap());
-        let r = f32::from_le_bytes(params[12..16].try_into().unwrap());
-        let j1 = f32::from_le_bytes(params[16..20].try_into().unwrap());
-        let j2 = f32::from_le_bytes(params[20..24].try_into().unwrap());
-        let j3 = f32::from_le_bytes(params[24..28].try_into().unwrap());
-        let j4 = f32::from_le_bytes(params[28..32].try_into().unwrap());
-
-        let pose = Pose {
-            x,
-            y,
-            z,
-            r,
-            j1,
-            j2,
-            j3,
-            j4,
-        };
-
-        Ok(pose)
+        self.execute(&GetPose).await
     }
 
     /// Move to given pose.
@@ -410,116 +405,589 @@ impl Dobot {
         Ok(handle)
     }
 
+    /// Move to given target with the selected motion.
+    pub async fn move_to_target<'a>(
+        &'a mut self,
+        target: Target,
+        motion: Motion,
+    ) -> DobotResult<WaitHandle<'a>> {
+        let mode = target.mode(motion).ok_or_else(|| {
+            DobotError::InvalidParameter(format!(
+                "{:?} motion is not supported for target {:?}",
+                motion, target
+            ))
+        })?;
+        let [a, b, c, d] = target.values();
+        let handle = self.set_ptp_cmd(a, b, c, d, mode).await?;
+        Ok(handle)
+    }
+
+    /// Move to given joint angles.
+    pub async fn move_joints_to<'a>(
+        &'a mut self,
+        j1: f32,
+        j2: f32,
+        j3: f32,
+        j4: f32,
+    ) -> DobotResult<WaitHandle<'a>> {
+        let handle = self
+            .move_to_target(Target::Joints { j1, j2, j3, j4 }, Motion::Joint)
+            .await?;
+        Ok(handle)
+    }
+
+    /// Move along a straight line by given offsets from the current pose.
+    pub async fn move_by<'a>(
+        &'a mut self,
+        x: f32,
+        y: f32,
+        z: f32,
+        r: f32,
+    ) -> DobotResult<WaitHandle<'a>> {
+        let handle = self
+            .move_to_target(Target::RelativeCartesian { x, y, z, r }, Motion::Linear)
+            .await?;
+        Ok(handle)
+    }
+
+    /// Rotate joints by given angle offsets from the current pose.
+    pub async fn move_joints_by<'a>(
+        &'a mut self,
+        j1: f32,
+        j2: f32,
+        j3: f32,
+        j4: f32,
+    ) -> DobotResult<WaitHandle<'a>> {
+        let handle = self
+            .move_to_target(Target::RelativeJoints { j1, j2, j3, j4 }, Motion::Joint)
+            .await?;
+        Ok(handle)
+    }
+
+    /// Send a typed command and decode its response.
+    ///
+    /// ```no_run
+    /// # async fn example(dobot: &mut dobot_fx24::Dobot) -> dobot_fx24::error::Result<()> {
+    /// use dobot_fx24::command::GetPose;
+    ///
+    /// let pose = dobot.execute(&GetPose).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute<C>(&mut self, command: &C) -> DobotResult<C::Response>
+    where
+        C: Command,
+    {
+        let response_msg = self.send_command(command.to_message()?).await?;
+        C::Response::decode(response_msg.params())
+    }
+
     /// Send user-defined request to Dobot and obtain response.
+    ///
+    /// Fails with [DobotError::UnexpectedResponse] if the response does not
+    /// answer the request, and with [DobotError::Timeout] if no response
+    /// arrives in time. Failed requests are sent again as the retry policy allows.
     pub async fn send_command(&mut self, request_msg: DobotMessage) -> DobotResult<DobotMessage> {
-        // send message
-        self.serial
-            .write_all(request_msg.to_bytes().as_slice())
-            .await?;
+        let mut retries = 0;
+        loop {
+            match self.exchange(&request_msg).await {
+                Err(err) if self.retry_policy.allows(&request_msg, &err, retries) => {
+                    retries += 1;
+                    rt::sleep(self.retry_policy.delay).await;
+                }
+                result => break result,
+            }
+        }
+    }
+
+    /// Set the time to wait for each response, or `None` to wait forever.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Get the time to wait for each response.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Set the policy deciding which failed requests are sent again.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    /// Get the policy deciding which failed requests are sent again.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// Set how queued commands are waited for by default.
+    pub fn set_wait_options(&mut self, wait_options: WaitOptions) {
+        self.wait_options = wait_options;
+    }
+
+    /// Get how queued commands are waited for by default.
+    pub fn wait_options(&self) -> &WaitOptions {
+        &self.wait_options
+    }
+
+    /// Get the index of the last queued command the firmware confirmed.
+    pub fn last_queued_index(&self) -> Option<u64> {
+        self.last_queued_index
+    }
+
+    /// Find out whether queued commands whose responses were lost reached the queue.
+    ///
+    /// The firmware assigns consecutive indexes, so lost commands would have
+    /// the indexes following [Dobot::last_queued_index]. Once the executed
+    /// index passes the last confirmed one, it is taken as the last queued
+    /// index and returned. `None` means no lost command has executed yet or
+    /// none arrived, so it is only safe to send them again once the queue is idle.
+    pub async fn reconcile_queued(&mut self) -> DobotResult<Option<u64>> {
+        let current_index = self.get_queued_cmd_current_index().await?;
+        if current_index > self.last_queued_index.unwrap_or(0) {
+            self.last_queued_index = Some(current_index);
+            return Ok(Some(current_index));
+        }
+        Ok(None)
+    }
+
+    /// Wait until the queued command of the ticket is executed.
+    ///
+    /// Fails with [DobotError::QueueCleared] if the queue was cleared after
+    /// the ticket was issued.
+    pub async fn wait_for(&mut self, command: &QueuedCommand) -> DobotResult<()> {
+        let wait_options = self.wait_options.clone();
+        self.wait_for_with(command, &wait_options).await
+    }
+
+    /// Wait until the queued command of the ticket is executed, polling as configured.
+    ///
+    /// Fails with [DobotError::Timeout] or [DobotError::Stalled] once a limit
+    /// in the options is exceeded.
+    pub async fn wait_for_with(
+        &mut self,
+        command: &QueuedCommand,
+        wait_options: &WaitOptions,
+    ) -> DobotResult<()> {
+        let mut progress = Progress::new(wait_options);
+        loop {
+            let current_index = self.executed_index(command).await?;
+            let executed = progress.update(command.index, current_index);
+            if let Ok(true) = executed {
+                break Ok(());
+            }
+            if progress.alarm_check_due(executed.is_err()) {
+                self.check_alarms(wait_options.alarm_policy).await?;
+            }
+            executed?;
+            rt::sleep(wait_options.poll_interval).await;
+        }
+    }
+
+    /// Wait until the queued commands of all tickets are executed.
+    ///
+    /// The firmware executes its queue in order, so this waits for the
+    /// ticket with the highest index.
+    pub async fn wait_all(&mut self, commands: &[QueuedCommand]) -> DobotResult<()> {
+        match commands.iter().max_by_key(|command| command.index) {
+            Some(last) => self.wait_for(last).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Wait until every command queued so far is executed.
+    pub async fn wait_until_idle(&mut self) -> DobotResult<()> {
+        match self.last_queued_index {
+            Some(index) => self.wait_for(&self.ticket(index)).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Fail with [DobotError::Alarm] if any alarm is raised, recovering as the policy says.
+    ///
+    /// Workspace alarms fail with [DobotError::OutOfWorkspace] instead.
+    pub(crate) async fn check_alarms(&mut self, alarm_policy: AlarmPolicy) -> DobotResult<()> {
+        let alarms = self.get_alarms_state().await?;
+        if alarms.is_empty() {
+            return Ok(());
+        }
+        match alarm_policy {
+            AlarmPolicy::Report => {}
+            AlarmPolicy::ClearAlarms => self.clear_all_alarms_state().await?,
+            AlarmPolicy::ClearAlarmsAndQueue => {
+                self.clear_all_alarms_state().await?;
+                self.set_queued_cmd_clear().await?;
+            }
+        }
+        if alarms.is_out_of_workspace() {
+            Err(DobotError::OutOfWorkspace(alarms))
+        } else {
+            Err(DobotError::Alarm(alarms))
+        }
+    }
+
+    /// Get the executed index to compare the ticket against.
+    pub(crate) async fn executed_index(&mut self, command: &QueuedCommand) -> DobotResult<u64> {
+        if !self.is_current(command) {
+            return Err(DobotError::QueueCleared);
+        }
+        self.get_queued_cmd_current_index().await
+    }
+
+    /// Check whether the ticket was issued since the last queue clear.
+    pub(crate) fn is_current(&self, command: &QueuedCommand) -> bool {
+        command.epoch == self.queue_epoch
+    }
 
-        // receive message
-        let response_msg = DobotMessage::from_async_reader(&mut self.serial).await?;
+    /// Issue a ticket for a command queued since the last clear.
+    pub(crate) fn ticket(&self, index: u64) -> QueuedCommand {
+        QueuedCommand {
+            index,
+            label: None,
+            epoch: self.queue_epoch,
+        }
+    }
+
+    /// Send a request and receive its response.
+    ///
+    /// All progress is kept in `self`, so the future can be dropped at any
+    /// await point: a partially written request is completed and the reply of
+    /// an abandoned request is drained by the next exchange.
+    async fn exchange(&mut self, request_msg: &DobotMessage) -> DobotResult<DobotMessage> {
+        self.settle().await?;
+
+        if (request_msg.id(), request_msg.rw()) == (CommandID::SetQueuedCmdClear, true) {
+            // the firmware restarts its indexes, even if the reply gets lost
+            self.queue_epoch += 1;
+            self.last_queued_index = None;
+        }
+
+        let timeout = self.timeout;
+        let exchange = async {
+            // send message
+            self.in_flight
+                .push_back((request_msg.id(), request_msg.rw()));
+            let mut frame = [0u8; MAX_FRAME_LEN];
+            let len = request_msg.encode_into(&mut frame)?;
+            self.outgoing.extend_from_slice(&frame[..len]);
+            self.flush().await?;
+
+            // receive message
+            let result = self.receive_message().await;
+            match &result {
+                Ok(response_msg)
+                    if (response_msg.id(), response_msg.rw())
+                        != (request_msg.id(), request_msg.rw()) =>
+                {
+                    // the actual response may still arrive and is drained later
+                    return Err(DobotError::UnexpectedResponse {
+                        expected: describe(request_msg.id(), request_msg.rw()),
+                        got: describe(response_msg.id(), response_msg.rw()),
+                    });
+                }
+                Ok(_) | Err(DobotError::IntegrityError { .. }) => {
+                    self.in_flight.pop_back();
+                }
+                Err(_) => {}
+            }
+            result
+        };
+        let response_msg = match timeout {
+            Some(timeout) => rt::timeout(timeout, exchange)
+                .await
+                .ok_or(DobotError::Timeout)??,
+            None => exchange.await?,
+        };
+
+        if request_msg.is_queued() {
+            self.last_queued_index = Some(queued_index(&response_msg)?);
+        }
 
         Ok(response_msg)
     }
+
+    /// Finish requests abandoned by cancelled or failed exchanges.
+    ///
+    /// Their replies are awaited for at most the configured timeout and
+    /// discarded, so they cannot be mistaken for the next response.
+    async fn settle(&mut self) -> DobotResult<()> {
+        if self.in_flight.is_empty() {
+            return Ok(());
+        }
+
+        let limit = self.timeout.unwrap_or(DEFAULT_TIMEOUT);
+        let settle = async {
+            self.flush().await?;
+            while !self.in_flight.is_empty() {
+                match self.receive_message().await {
+                    Ok(msg) => {
+                        let key = (msg.id(), msg.rw());
+                        // earlier requests whose replies were skipped are lost
+                        if let Some(pos) = self.in_flight.iter().position(|pending| *pending == key)
+                        {
+                            self.in_flight.drain(..=pos);
+                        }
+                    }
+                    Err(DobotError::IntegrityError { .. }) => {
+                        self.in_flight.pop_front();
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+            Ok(())
+        };
+        let result = rt::timeout(limit, settle).await;
+        if !matches!(result, Some(Ok(()))) {
+            self.abandon();
+        }
+        match result {
+            Some(Err(err @ DobotError::IoError(_))) => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Give up on the replies that did not arrive in time.
+    ///
+    /// Bytes of a late reply are dropped so they cannot complete a later
+    /// message. Requests not yet written are dropped as well, but a partly
+    /// written one is finished by the next flush and its reply drained.
+    fn abandon(&mut self) {
+        self.decoder.clear();
+
+        let mut unsent = 0;
+        let mut pos = self.partial;
+        while pos < self.outgoing.len() {
+            pos += frame_len(&self.outgoing[pos..]);
+            unsent += 1;
+        }
+        self.outgoing.truncate(self.partial);
+
+        // the unsent requests are the last ones in flight
+        let written = self.in_flight.len().saturating_sub(unsent);
+        self.in_flight.truncate(written);
+        let answerable = usize::from(self.partial > 0);
+        self.in_flight.drain(..written.saturating_sub(answerable));
+    }
+
+    async fn flush(&mut self) -> DobotResult<()> {
+        while !self.outgoing.is_empty() {
+            let len = self.transport.write(&self.outgoing).await?;
+            if len == 0 {
+                return Err(StdIoError::from(ErrorKind::WriteZero).into());
+            }
+            // remember how much of the frame on the wire is still unwritten
+            let mut end = self.partial;
+            while end < len {
+                end += frame_len(&self.outgoing[end..]);
+            }
+            self.partial = end - len;
+            self.outgoing.drain(..len);
+        }
+        self.transport.flush().await?;
+        Ok(())
+    }
+
+    /// Get the number of received bytes skipped while resynchronizing on message headers.
+    pub fn bytes_discarded(&self) -> u64 {
+        self.decoder.bytes_discarded()
+    }
+
+    async fn receive_message(&mut self) -> DobotResult<DobotMessage> {
+        loop {
+            if let Some(result) = self.decoder.decode() {
+                break result;
+            }
+
+            let mut buffer = [0u8; 64];
+            let len = self.transport.read(&mut buffer).await?;
+            if len == 0 {
+                break Err(StdIoError::from(ErrorKind::UnexpectedEof).into());
+            }
+            self.decoder.extend(&buffer[..len]);
+        }
+    }
+}
+
+/// Get the length of the encoded frame at the start of `bytes`.
+fn frame_len(bytes: &[u8]) -> usize {
+    // header, length, the id, control and params it counts, and checksum
+    HEADER.len() + 1 + bytes[HEADER.len()] as usize + 1
+}
+
+fn describe(id: CommandID, rw: bool) -> String {
+    format!("{:?} {} response", id, if rw { "write" } else { "read" })
+}
+
+/// Get the queued command index carried by a response.
+pub(crate) fn queued_index(response_msg: &DobotMessage) -> DobotResult<u64> {
+    u64::decode(response_msg.params())
 }
 
 pub struct WaitHandle<'a> {
-    command_index: u64,
+    command: QueuedCommand,
     dobot: &'a mut Dobot,
 }
 
 impl<'a> WaitHandle<'a> {
     pub(crate) fn new(dobot: &'a mut Dobot, command_index: u64) -> Self {
         Self {
-            command_index,
+            command: dobot.ticket(command_index),
             dobot,
         }
     }
 
     pub async fn wait(self) -> DobotResult<()> {
-        loop {
-            let current_index = self.dobot.get_queued_cmd_current_index().await?;
-            if current_index == self.command_index {
-                break Ok(());
-            }
-        }
+        self.dobot.wait_for(&self.command).await
+    }
+
+    /// Wait for the command, polling as configured instead of the defaults.
+    pub async fn wait_with(self, wait_options: WaitOptions) -> DobotResult<()> {
+        self.dobot.wait_for_with(&self.command, &wait_options).await
     }
-    
+
     pub fn get_queued_index(self) -> u64 {
-        self.command_index
+        self.command.index
+    }
+
+    /// Release the borrow on the [Dobot] and get an owned ticket for the command.
+    pub fn detach(self) -> QueuedCommand {
+        self.command
+    }
+
+    /// Release the borrow on the [Dobot] and get a labeled ticket for the command.
+    pub fn detach_labeled<L>(self, label: L) -> QueuedCommand
+    where
+        L: Into<String>,
+    {
+        self.detach().with_label(label)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive)]
-pub enum CommandID {
-    GetSetDeviceSn = 0,
-    GetSetDeviceName = 1,
-    GetDeviceVersion = 2,
-    GetDeviceWithL = 3,
-    GetDeviceTime = 4,
-    // GetDeviceId = 0, // 4
-    GetPose = 10,
-    ResetPose = 11,
-    GetPoseL = 13,
-    GetAlarmsState = 20,
-    ClearAllAlarmsState = 21,
-    GetSetHomeParams = 30,
-    SetHomeCmd = 31,
-    // GetSetAutoLeveling = 0, // 30
-    GetSetHHTTrigMode = 40,
-    GetSetHHTTrigOutputEnabled = 41,
-    GetHHTTrigOutput = 42,
-    GetSetEndEffectorParams = 60,
-    GetSetEndEffectorLaser = 61,
-    GetSetEndEffectorSuctionCup = 62,
-    GetSetEndEffectorGripper = 63,
-    GetSetJogJointParams = 70,
-    GetSetJogCoordinateParams = 71,
-    GetSetJogCommonParams = 72,
-    SetJogCmd = 73,
-    GetSetJogLParams = 74,
-    GetSetPtpJointParams = 80,
-    GetSetPtpCoordinateParams = 81,
-    GetSetPtpJumpParams = 82,
-    GetSetPtpCommonParams = 83,
-    SetPtpCmd = 84,
-    GetSetPtpLParams = 85,
-    SetPtpWithLCmd = 86,
-    GetSetPtpJump2Params = 87,
-    SetPtpPoCmd = 88,
-    SetPtpPoWithLCmd = 89,
-    GetSetCpParams = 90,
-    SetCpCmd = 91,
-    SetCpLeCmd = 92,
-    GetSetArcParams = 100,
-    SetSetArcCmd = 101,
-    SetWaitCmd = 110,
-    SetTrigCmd = 120,
-    GetSetIoMultiplexing = 130,
-    GetSetIoDo = 131,
-    GetSetIoPwm = 132,
-    GetIoDi = 133,
-    GetIoAdc = 134,
-    SetEMotor = 135,
-    GetSetColorSensor = 137,
-    GetSetIrSwitch = 138,
-    GetSetAngleSensorStaticError = 140,
-    GetSetWifiConfigMode = 150,
-    GetSetWifiSsid = 151,
-    GetSetWifiPassword = 152,
-    GetSetWifiAddress = 153,
-    GetSetWifiNetmask = 154,
-    GetSetWifiGateway = 155,
-    GetSetWifiDns = 156,
-    GetSetWifiConnectStatus = 157,
-    SetLostStepParams = 170,
-    SetLostStepCmd = 171,
-    SetQueuedCmdStartExec = 240,
-    SetQueuedCmdStopExec = 241,
-    SetQueuedCmdForceStopExec = 242,
-    SetQueuedCmdStartDownload = 243,
-    SetQueuedCmdStopDownload = 244,
-    SetQueuedCmdClear = 245,
-    SetQueuedCmdCurrentIndex = 246,
+/// An owned ticket for a command in the firmware queue.
+///
+/// Unlike [WaitHandle], a ticket does not borrow the [Dobot], so many
+/// commands can be enqueued before waiting on some of them with
+/// [Dobot::wait_for] or [Dobot::wait_all].
+///
+/// Tickets are only valid until the queue is cleared, since the firmware
+/// starts counting indexes again afterwards.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueuedCommand {
+    /// The index the firmware assigned to the command.
+    pub index: u64,
+    /// An optional name to tell checkpoints apart.
+    pub label: Option<String>,
+    epoch: u64,
+}
+
+impl QueuedCommand {
+    /// Attach a label to the ticket.
+    pub fn with_label<L>(mut self, label: L) -> Self
+    where
+        L: Into<String>,
+    {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mode, Motion, Target};
+
+    const MOTIONS: [Motion; 3] = [Motion::Jump, Motion::Joint, Motion::Linear];
+
+    #[test]
+    fn target_mode_test() {
+        let cartesian = Target::Cartesian {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            r: 4.0,
+        };
+        let joints = Target::Joints {
+            j1: 1.0,
+            j2: 2.0,
+            j3: 3.0,
+            j4: 4.0,
+        };
+        let relative_cartesian = Target::RelativeCartesian {
+            x: 1.0,
+            y: 2.0,
+            z: 3.0,
+            r: 4.0,
+        };
+        let relative_joints = Target::RelativeJoints {
+            j1: 1.0,
+            j2: 2.0,
+            j3: 3.0,
+            j4: 4.0,
+        };
+
+        let modes = |target: Target| MOTIONS.map(|motion| target.mode(motion));
+        assert_eq!(
+            modes(cartesian),
+            [
+                Some(Mode::MODE_PTP_JUMP_XYZ),
+                Some(Mode::MODE_PTP_MOVJ_XYZ),
+                Some(Mode::MODE_PTP_MOVL_XYZ),
+            ]
+        );
+        assert_eq!(
+            modes(joints),
+            [
+                Some(Mode::MODE_PTP_JUMP_ANGLE),
+                Some(Mode::MODE_PTP_MOVJ_ANGLE),
+                Some(Mode::MODE_PTP_MOVL_ANGLE),
+            ]
+        );
+        // the firmware cannot jump by an offset or move linearly by joint offsets
+        assert_eq!(
+            modes(relative_cartesian),
+            [
+                None,
+                Some(Mode::MODE_PTP_MOVJ_XYZ_INC),
+                Some(Mode::MODE_PTP_MOVL_INC),
+            ]
+        );
+        assert_eq!(
+            modes(relative_joints),
+            [None, Some(Mode::MODE_PTP_MOVJ_INC), None]
+        );
+    }
+
+    #[test]
+    fn target_values_test() {
+        let values = [1.5, -2.0, 30.0, 90.0];
+        let [a, b, c, d] = values;
+        let targets = [
+            Target::Cartesian {
+                x: a,
+                y: b,
+                z: c,
+                r: d,
+            },
+            Target::Joints {
+                j1: a,
+                j2: b,
+                j3: c,
+                j4: d,
+            },
+            Target::RelativeCartesian {
+                x: a,
+                y: b,
+                z: c,
+                r: d,
+            },
+            Target::RelativeJoints {
+                j1: a,
+                j2: b,
+                j3: c,
+                j4: d,
+            },
+        ];
+        for target in targets.iter() {
+            assert_eq!(target.values(), values, "{:?}", target);
+        }
+    }
 }
@@ -0,0 +1,423 @@
+//! Blocking API for programs without an async runtime.
+//!
+//! [Dobot] wraps the async [crate::Dobot] together with its own
+//! current-thread runtime, and each method blocks until the request is
+//! answered.
+//!
+//! ```no_run
+//! use dobot_fx24::blocking::Dobot;
+//!
+//! # fn main() -> dobot_fx24::error::Result<()> {
+//! let mut dobot = Dobot::open("/dev/ttyUSB0")?;
+//! dobot.move_to(200.0, 0.0, 0.0, 0.0)?.wait()?;
+//! println!("pose {:?}", dobot.get_pose()?);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Methods must not be called from within an async runtime, since the
+//! internal runtime cannot block inside another one.
+use crate::{
+    alarm::Alarms,
+    base::{self, Mode, Motion, Pose, QueuedCommand, Target},
+    command::Command,
+    discovery::DiscoveredDobot,
+    error::Result as DobotResult,
+    message::DobotMessage,
+    retry::RetryPolicy,
+    transport::Transport,
+    wait::WaitOptions,
+};
+use std::{path::Path, time::Duration};
+use tokio::runtime::{Builder, Runtime};
+
+/// The Dobot robot arm controller type with blocking methods.
+pub struct Dobot {
+    runtime: Runtime,
+    dobot: base::Dobot,
+}
+
+impl Dobot {
+    /// Create controller object from device file.
+    pub fn open<P>(path: P) -> DobotResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let runtime = new_runtime()?;
+        let dobot = runtime.block_on(base::Dobot::open(path))?;
+        Ok(Self { runtime, dobot })
+    }
+
+    /// Find the controllers connected to serial ports.
+    pub fn discover() -> DobotResult<Vec<DiscoveredDobot>> {
+        new_runtime()?.block_on(base::Dobot::discover())
+    }
+
+    /// Open the controller with the given serial number.
+    pub fn open_by_serial(serial: &str) -> DobotResult<Self> {
+        let runtime = new_runtime()?;
+        let dobot = runtime.block_on(base::Dobot::open_by_serial(serial))?;
+        Ok(Self { runtime, dobot })
+    }
+
+    /// Open the controller with the given name.
+    pub fn open_by_name(name: &str) -> DobotResult<Self> {
+        let runtime = new_runtime()?;
+        let dobot = runtime.block_on(base::Dobot::open_by_name(name))?;
+        Ok(Self { runtime, dobot })
+    }
+
+    /// Create controller object over an arbitrary transport.
+    pub fn from_transport<T>(transport: T) -> DobotResult<Self>
+    where
+        T: Transport + 'static,
+    {
+        Self::from_transport_with(|| transport)
+    }
+
+    /// Create controller object over a transport created by the closure.
+    ///
+    /// The closure runs in the internal runtime, so the transport may spawn
+    /// tasks on it, e.g. [Simulator::connect](crate::sim::Simulator::connect).
+    pub fn from_transport_with<F, T>(connect: F) -> DobotResult<Self>
+    where
+        F: FnOnce() -> T,
+        T: Transport + 'static,
+    {
+        let runtime = new_runtime()?;
+        let transport = {
+            let _guard = runtime.enter();
+            connect()
+        };
+        let dobot = runtime.block_on(base::Dobot::from_transport(transport))?;
+        Ok(Self { runtime, dobot })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_ptp_joint_params(
+        &mut self,
+        v_x: f32,
+        v_y: f32,
+        v_z: f32,
+        v_r: f32,
+        a_x: f32,
+        a_y: f32,
+        a_z: f32,
+        a_r: f32,
+    ) -> DobotResult<WaitHandle<'_>> {
+        let handle = self.runtime.block_on(
+            self.dobot
+                .set_ptp_joint_params(v_x, v_y, v_z, v_r, a_x, a_y, a_z, a_r),
+        )?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    pub fn set_cp_cmd(&mut self, x: f32, y: f32, z: f32) -> DobotResult<WaitHandle<'_>> {
+        let handle = self.runtime.block_on(self.dobot.set_cp_cmd(x, y, z))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    pub fn set_ptp_coordinate_params(
+        &mut self,
+        velocity: f32,
+        acceleration: f32,
+    ) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.set_ptp_coordinate_params(velocity, acceleration))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    pub fn set_ptp_jump_params(&mut self, jump: f32, limit: f32) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.set_ptp_jump_params(jump, limit))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    pub fn set_ptp_common_params(
+        &mut self,
+        velocity: f32,
+        acceleration: f32,
+    ) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.set_ptp_common_params(velocity, acceleration))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    pub fn set_ptp_cmd(
+        &mut self,
+        x: f32,
+        y: f32,
+        z: f32,
+        r: f32,
+        mode: Mode,
+    ) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.set_ptp_cmd(x, y, z, r, mode))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    pub fn set_end_effector_suction_cup(&mut self, enable: bool) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.set_end_effector_suction_cup(enable))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    pub fn set_end_effector_gripper(&mut self, enable: bool) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.set_end_effector_gripper(enable))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    pub fn set_queued_cmd_start_exec(&mut self) -> DobotResult<()> {
+        self.runtime
+            .block_on(self.dobot.set_queued_cmd_start_exec())
+    }
+
+    pub fn set_queued_cmd_stop_exec(&mut self) -> DobotResult<()> {
+        self.runtime.block_on(self.dobot.set_queued_cmd_stop_exec())
+    }
+
+    pub fn set_queued_cmd_clear(&mut self) -> DobotResult<()> {
+        self.runtime.block_on(self.dobot.set_queued_cmd_clear())
+    }
+
+    /// Get the serial number of the device.
+    pub fn get_device_sn(&mut self) -> DobotResult<String> {
+        self.runtime.block_on(self.dobot.get_device_sn())
+    }
+
+    /// Get the name of the device.
+    pub fn get_device_name(&mut self) -> DobotResult<String> {
+        self.runtime.block_on(self.dobot.get_device_name())
+    }
+
+    /// Get the number of commands the firmware queue can still take.
+    pub fn get_queued_cmd_left_space(&mut self) -> DobotResult<u32> {
+        self.runtime
+            .block_on(self.dobot.get_queued_cmd_left_space())
+    }
+
+    /// Get the alarms raised by the controller.
+    pub fn get_alarms_state(&mut self) -> DobotResult<Alarms> {
+        self.runtime.block_on(self.dobot.get_alarms_state())
+    }
+
+    /// Clear all alarms, which lets the queue continue.
+    pub fn clear_all_alarms_state(&mut self) -> DobotResult<()> {
+        self.runtime.block_on(self.dobot.clear_all_alarms_state())
+    }
+
+    pub fn get_queued_cmd_current_index(&mut self) -> DobotResult<u64> {
+        self.runtime
+            .block_on(self.dobot.get_queued_cmd_current_index())
+    }
+
+    /// Grips on end effector.
+    pub fn grip(&mut self) -> DobotResult<WaitHandle<'_>> {
+        let handle = self.runtime.block_on(self.dobot.grip())?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    /// Releases gripper on end effector.
+    pub fn release(&mut self) -> DobotResult<WaitHandle<'_>> {
+        let handle = self.runtime.block_on(self.dobot.release())?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    /// Starts the calibration process.
+    pub fn set_home(&mut self) -> DobotResult<WaitHandle<'_>> {
+        let handle = self.runtime.block_on(self.dobot.set_home())?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    /// Get the current pose of robot.
+    pub fn get_pose(&mut self) -> DobotResult<Pose> {
+        self.runtime.block_on(self.dobot.get_pose())
+    }
+
+    /// Move to given pose.
+    pub fn move_to(&mut self, x: f32, y: f32, z: f32, r: f32) -> DobotResult<WaitHandle<'_>> {
+        let handle = self.runtime.block_on(self.dobot.move_to(x, y, z, r))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    /// Move to given target with the selected motion.
+    pub fn move_to_target(
+        &mut self,
+        target: Target,
+        motion: Motion,
+    ) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.move_to_target(target, motion))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    /// Move to given joint angles.
+    pub fn move_joints_to(
+        &mut self,
+        j1: f32,
+        j2: f32,
+        j3: f32,
+        j4: f32,
+    ) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.move_joints_to(j1, j2, j3, j4))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    /// Move along a straight line by given offsets from the current pose.
+    pub fn move_by(&mut self, x: f32, y: f32, z: f32, r: f32) -> DobotResult<WaitHandle<'_>> {
+        let handle = self.runtime.block_on(self.dobot.move_by(x, y, z, r))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    /// Rotate joints by given angle offsets from the current pose.
+    pub fn move_joints_by(
+        &mut self,
+        j1: f32,
+        j2: f32,
+        j3: f32,
+        j4: f32,
+    ) -> DobotResult<WaitHandle<'_>> {
+        let handle = self
+            .runtime
+            .block_on(self.dobot.move_joints_by(j1, j2, j3, j4))?;
+        Ok(WaitHandle::new(&self.runtime, handle))
+    }
+
+    /// Send a typed command and decode its response.
+    pub fn execute<C>(&mut self, command: &C) -> DobotResult<C::Response>
+    where
+        C: Command,
+    {
+        self.runtime.block_on(self.dobot.execute(command))
+    }
+
+    /// Send user-defined request to Dobot and obtain response.
+    pub fn send_command(&mut self, request_msg: DobotMessage) -> DobotResult<DobotMessage> {
+        self.runtime.block_on(self.dobot.send_command(request_msg))
+    }
+
+    /// Set the time to wait for each response, or `None` to wait forever.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.dobot.set_timeout(timeout);
+    }
+
+    /// Get the time to wait for each response.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.dobot.timeout()
+    }
+
+    /// Set the policy deciding which failed requests are sent again.
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.dobot.set_retry_policy(retry_policy);
+    }
+
+    /// Get the policy deciding which failed requests are sent again.
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        self.dobot.retry_policy()
+    }
+
+    /// Set how queued commands are waited for by default.
+    pub fn set_wait_options(&mut self, wait_options: WaitOptions) {
+        self.dobot.set_wait_options(wait_options);
+    }
+
+    /// Get how queued commands are waited for by default.
+    pub fn wait_options(&self) -> &WaitOptions {
+        self.dobot.wait_options()
+    }
+
+    /// Get the index of the last queued command the firmware confirmed.
+    pub fn last_queued_index(&self) -> Option<u64> {
+        self.dobot.last_queued_index()
+    }
+
+    /// Find out whether a queued command whose response was lost reached the queue.
+    ///
+    /// See [crate::Dobot::reconcile_queued].
+    pub fn reconcile_queued(&mut self) -> DobotResult<Option<u64>> {
+        self.runtime.block_on(self.dobot.reconcile_queued())
+    }
+
+    /// Wait until the queued command of the ticket is executed.
+    pub fn wait_for(&mut self, command: &QueuedCommand) -> DobotResult<()> {
+        self.runtime.block_on(self.dobot.wait_for(command))
+    }
+
+    /// Wait until the queued command of the ticket is executed, polling as configured.
+    pub fn wait_for_with(
+        &mut self,
+        command: &QueuedCommand,
+        wait_options: &WaitOptions,
+    ) -> DobotResult<()> {
+        self.runtime
+            .block_on(self.dobot.wait_for_with(command, wait_options))
+    }
+
+    /// Wait until the queued commands of all tickets are executed.
+    pub fn wait_all(&mut self, commands: &[QueuedCommand]) -> DobotResult<()> {
+        self.runtime.block_on(self.dobot.wait_all(commands))
+    }
+
+    /// Wait until every command queued so far is executed.
+    pub fn wait_until_idle(&mut self) -> DobotResult<()> {
+        self.runtime.block_on(self.dobot.wait_until_idle())
+    }
+
+    /// Get the number of received bytes skipped while resynchronizing on message headers.
+    pub fn bytes_discarded(&self) -> u64 {
+        self.dobot.bytes_discarded()
+    }
+}
+
+/// Blocking counterpart of [base::WaitHandle].
+pub struct WaitHandle<'a> {
+    runtime: &'a Runtime,
+    handle: base::WaitHandle<'a>,
+}
+
+impl<'a> WaitHandle<'a> {
+    fn new(runtime: &'a Runtime, handle: base::WaitHandle<'a>) -> Self {
+        Self { runtime, handle }
+    }
+
+    pub fn wait(self) -> DobotResult<()> {
+        self.runtime.block_on(self.handle.wait())
+    }
+
+    /// Wait for the command, polling as configured instead of the defaults.
+    pub fn wait_with(self, wait_options: WaitOptions) -> DobotResult<()> {
+        self.runtime.block_on(self.handle.wait_with(wait_options))
+    }
+
+    pub fn get_queued_index(self) -> u64 {
+        self.handle.get_queued_index()
+    }
+
+    /// Release the borrow on the [Dobot] and get an owned ticket for the command.
+    pub fn detach(self) -> QueuedCommand {
+        self.handle.detach()
+    }
+
+    /// Release the borrow on the [Dobot] and get a labeled ticket for the command.
+    pub fn detach_labeled<L>(self, label: L) -> QueuedCommand
+    where
+        L: Into<String>,
+    {
+        self.handle.detach_labeled(label)
+    }
+}
+
+fn new_runtime() -> DobotResult<Runtime> {
+    let runtime = Builder::new_current_thread().enable_all().build()?;
+    Ok(runtime)
+}
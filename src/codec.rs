@@ -0,0 +1,101 @@
+//! Framing of byte streams into messages for tokio-util.
+//!
+//! ```no_run
+//! # async fn example(stream: tokio::io::DuplexStream) -> dobot_fx24::error::Result<()> {
+//! use dobot_fx24::codec::DobotCodec;
+//! use futures::StreamExt;
+//! use tokio_util::codec::Framed;
+//!
+//! let mut frames = Framed::new(stream, DobotCodec::new());
+//! while let Some(msg) = frames.next().await {
+//!     println!("{:?}", msg?.id());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    error::Error as DobotError,
+    message::{scan, DobotMessage, DobotMessageRef, Scan},
+};
+use bytes::{Buf, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Codec turning a byte stream into a stream and sink of [DobotMessage]s.
+///
+/// Decoding resynchronizes on the `0xAA 0xAA` header like
+/// [MessageDecoder](crate::message::MessageDecoder). Frames with a wrong
+/// checksum are skipped rather than returned as errors, since an error ends
+/// a [Framed](tokio_util::codec::Framed) stream; they are counted instead.
+#[derive(Clone, Debug, Default)]
+pub struct DobotCodec {
+    bytes_discarded: u64,
+    corrupted_frames: u64,
+}
+
+impl DobotCodec {
+    /// Create codec.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the total number of bytes skipped while searching for messages.
+    pub fn bytes_discarded(&self) -> u64 {
+        self.bytes_discarded
+    }
+
+    /// Get the number of frames skipped because of a wrong checksum.
+    pub fn corrupted_frames(&self) -> u64 {
+        self.corrupted_frames
+    }
+}
+
+impl Decoder for DobotCodec {
+    type Item = DobotMessage;
+    type Error = DobotError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let len = match scan(src) {
+                Scan::Garbage(count) => {
+                    src.advance(count);
+                    self.bytes_discarded += count as u64;
+                    continue;
+                }
+                Scan::Incomplete => return Ok(None),
+                Scan::Frame(len) => len,
+            };
+
+            match DobotMessageRef::from_bytes(&src[..len]) {
+                Ok(msg) => {
+                    let msg = msg.to_message();
+                    src.advance(len);
+                    return Ok(Some(msg));
+                }
+                Err(_) => {
+                    src.advance(1);
+                    self.bytes_discarded += 1;
+                    self.corrupted_frames += 1;
+                }
+            }
+        }
+    }
+}
+
+impl Encoder<DobotMessage> for DobotCodec {
+    type Error = DobotError;
+
+    fn encode(&mut self, item: DobotMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        Encoder::<&DobotMessage>::encode(self, &item, dst)
+    }
+}
+
+impl Encoder<&DobotMessage> for DobotCodec {
+    type Error = DobotError;
+
+    fn encode(&mut self, item: &DobotMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let start = dst.len();
+        dst.resize(start + item.encoded_len(), 0);
+        item.encode_into(&mut dst[start..])?;
+        Ok(())
+    }
+}
@@ -0,0 +1,456 @@
+//! Typed protocol commands.
+//!
+//! Each command is a struct implementing [Command], which knows how to
+//! encode its params and which [Response] it receives. Commands the crate
+//! does not wrap yet can be defined downstream and sent with
+//! [Dobot::execute](crate::Dobot::execute).
+use crate::{
+    alarm::Alarms,
+    base::{CommandID, Mode, Pose},
+    error::{Error as DobotError, Result as DobotResult},
+    message::{DobotMessage, ParamsReader, ParamsWriter},
+    protocol_params,
+};
+
+/// A command of Dobot protocol.
+pub trait Command {
+    /// The command ID.
+    const ID: CommandID;
+    /// Whether the command writes (`true`) or reads (`false`).
+    const RW: bool;
+    /// Whether the command enters the firmware queue.
+    const IS_QUEUED: bool;
+    /// The response received for the command.
+    ///
+    /// Queued commands receive their queued command index as `u64`.
+    type Response: Response;
+
+    /// Serialize the params of the command.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Build the request message of the command.
+    fn to_message(&self) -> DobotResult<DobotMessage> {
+        DobotMessage::new(Self::ID, Self::RW, Self::IS_QUEUED, self.encode())
+    }
+}
+
+/// A value decoded from the params of a response.
+///
+/// Implementations reading protocol fields can use [read_response] to report
+/// params of the wrong length as [DobotError::UnexpectedResponse].
+pub trait Response: Sized {
+    /// Deserialize the params of a response.
+    fn decode(params: &[u8]) -> DobotResult<Self>;
+}
+
+impl Response for () {
+    fn decode(params: &[u8]) -> DobotResult<Self> {
+        read_response(params, |_| Ok(()))
+    }
+}
+
+impl Response for u32 {
+    fn decode(params: &[u8]) -> DobotResult<Self> {
+        read_response(params, ParamsReader::u32)
+    }
+}
+
+impl Response for u64 {
+    fn decode(params: &[u8]) -> DobotResult<Self> {
+        read_response(params, ParamsReader::u64)
+    }
+}
+
+impl Response for Vec<u8> {
+    fn decode(params: &[u8]) -> DobotResult<Self> {
+        Ok(params.to_vec())
+    }
+}
+
+impl Response for String {
+    fn decode(params: &[u8]) -> DobotResult<Self> {
+        // the firmware may pad strings with NUL bytes
+        let end = params
+            .iter()
+            .rposition(|byte| *byte != 0)
+            .map_or(0, |pos| pos + 1);
+        String::from_utf8(params[..end].to_vec())
+            .map_err(|_| DobotError::DeserializeError("params are not UTF-8 text".to_string()))
+    }
+}
+
+impl Response for Alarms {
+    fn decode(params: &[u8]) -> DobotResult<Self> {
+        Ok(Alarms::from_bytes(params))
+    }
+}
+
+/// Read the params of a response, which must be consumed entirely.
+///
+/// Params that are too short or too long fail with
+/// [DobotError::UnexpectedResponse], like any other response that does not
+/// answer the request.
+pub fn read_response<'a, T, F>(params: &'a [u8], read: F) -> DobotResult<T>
+where
+    F: FnOnce(&mut ParamsReader<'a>) -> DobotResult<T>,
+{
+    let mut reader = ParamsReader::new(params);
+    let result = read(&mut reader).and_then(|value| reader.finish().map(|()| value));
+    result.map_err(|err| match err {
+        DobotError::ParamsTruncated { needed, remaining } => DobotError::UnexpectedResponse {
+            expected: format!(
+                "at least {} bytes of params",
+                params.len() - remaining + needed
+            ),
+            got: format!("{} bytes", params.len()),
+        },
+        DobotError::TrailingParams(len) => DobotError::UnexpectedResponse {
+            expected: format!("{} bytes of params", params.len() - len),
+            got: format!("{} bytes", params.len()),
+        },
+        err => err,
+    })
+}
+
+/// Get the serial number of the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GetDeviceSn;
+
+impl Command for GetDeviceSn {
+    const ID: CommandID = CommandID::GetSetDeviceSn;
+    const RW: bool = false;
+    const IS_QUEUED: bool = false;
+    type Response = String;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Get the name of the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GetDeviceName;
+
+impl Command for GetDeviceName {
+    const ID: CommandID = CommandID::GetSetDeviceName;
+    const RW: bool = false;
+    const IS_QUEUED: bool = false;
+    type Response = String;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Get the current pose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GetPose;
+
+impl Command for GetPose {
+    const ID: CommandID = CommandID::GetPose;
+    const RW: bool = false;
+    const IS_QUEUED: bool = false;
+    type Response = Pose;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Get the alarms raised by the controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GetAlarmsState;
+
+impl Command for GetAlarmsState {
+    const ID: CommandID = CommandID::GetAlarmsState;
+    const RW: bool = false;
+    const IS_QUEUED: bool = false;
+    type Response = Alarms;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Clear all alarms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ClearAllAlarmsState;
+
+impl Command for ClearAllAlarmsState {
+    const ID: CommandID = CommandID::ClearAllAlarmsState;
+    const RW: bool = true;
+    const IS_QUEUED: bool = false;
+    type Response = ();
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Start the homing procedure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SetHomeCmd;
+
+impl Command for SetHomeCmd {
+    const ID: CommandID = CommandID::SetHomeCmd;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Switch the suction cup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetEndEffectorSuctionCup {
+    pub enable: bool,
+}
+
+impl Command for SetEndEffectorSuctionCup {
+    const ID: CommandID = CommandID::GetSetEndEffectorSuctionCup;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        ParamsWriter::new().bool(true).bool(self.enable).finish()
+    }
+}
+
+/// Switch the gripper.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetEndEffectorGripper {
+    pub enable: bool,
+}
+
+impl Command for SetEndEffectorGripper {
+    const ID: CommandID = CommandID::GetSetEndEffectorGripper;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        ParamsWriter::new().bool(true).bool(self.enable).finish()
+    }
+}
+
+protocol_params! {
+    /// Set the velocity and acceleration of each joint in PTP mode.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SetPtpJointParams {
+        pub velocity: [f32; 4],
+        pub acceleration: [f32; 4],
+    }
+}
+
+impl Command for SetPtpJointParams {
+    const ID: CommandID = CommandID::GetSetPtpJointParams;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        ParamsWriter::new().params(self).finish()
+    }
+}
+
+protocol_params! {
+    /// Set the Cartesian velocity and acceleration in PTP mode.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SetPtpCoordinateParams {
+        pub xyz_velocity: f32,
+        pub r_velocity: f32,
+        pub xyz_acceleration: f32,
+        pub r_acceleration: f32,
+    }
+}
+
+impl Command for SetPtpCoordinateParams {
+    const ID: CommandID = CommandID::GetSetPtpCoordinateParams;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        ParamsWriter::new().params(self).finish()
+    }
+}
+
+protocol_params! {
+    /// Set the lift height and the maximum height of jump motions.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SetPtpJumpParams {
+        pub jump_height: f32,
+        pub z_limit: f32,
+    }
+}
+
+impl Command for SetPtpJumpParams {
+    const ID: CommandID = CommandID::GetSetPtpJumpParams;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        ParamsWriter::new().params(self).finish()
+    }
+}
+
+protocol_params! {
+    /// Set the velocity and acceleration ratios in PTP mode.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct SetPtpCommonParams {
+        pub velocity_ratio: f32,
+        pub acceleration_ratio: f32,
+    }
+}
+
+impl Command for SetPtpCommonParams {
+    const ID: CommandID = CommandID::GetSetPtpCommonParams;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        ParamsWriter::new().params(self).finish()
+    }
+}
+
+/// Move point to point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SetPtpCmd {
+    pub mode: Mode,
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub r: f32,
+}
+
+impl Command for SetPtpCmd {
+    const ID: CommandID = CommandID::SetPtpCmd;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        ParamsWriter::new()
+            .u8(self.mode as u8)
+            .f32s(&[self.x, self.y, self.z, self.r])
+            .finish()
+    }
+}
+
+/// Move continuously to an absolute point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SetCpCmd {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Command for SetCpCmd {
+    const ID: CommandID = CommandID::SetCpCmd;
+    const RW: bool = true;
+    const IS_QUEUED: bool = true;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        ParamsWriter::new()
+            .u8(0x01)
+            .f32s(&[self.x, self.y, self.z])
+            .u8(0x00)
+            .finish()
+    }
+}
+
+/// Start executing the firmware queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SetQueuedCmdStartExec;
+
+impl Command for SetQueuedCmdStartExec {
+    const ID: CommandID = CommandID::SetQueuedCmdStartExec;
+    const RW: bool = true;
+    const IS_QUEUED: bool = false;
+    type Response = ();
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Stop executing the firmware queue after the current command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SetQueuedCmdStopExec;
+
+impl Command for SetQueuedCmdStopExec {
+    const ID: CommandID = CommandID::SetQueuedCmdStopExec;
+    const RW: bool = true;
+    const IS_QUEUED: bool = false;
+    type Response = ();
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Stop executing the firmware queue immediately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SetQueuedCmdForceStopExec;
+
+impl Command for SetQueuedCmdForceStopExec {
+    const ID: CommandID = CommandID::SetQueuedCmdForceStopExec;
+    const RW: bool = true;
+    const IS_QUEUED: bool = false;
+    type Response = ();
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Drop all commands in the firmware queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SetQueuedCmdClear;
+
+impl Command for SetQueuedCmdClear {
+    const ID: CommandID = CommandID::SetQueuedCmdClear;
+    const RW: bool = true;
+    const IS_QUEUED: bool = false;
+    type Response = ();
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Get the index of the last executed queued command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GetQueuedCmdCurrentIndex;
+
+impl Command for GetQueuedCmdCurrentIndex {
+    const ID: CommandID = CommandID::SetQueuedCmdCurrentIndex;
+    const RW: bool = false;
+    const IS_QUEUED: bool = false;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// Get the number of commands the firmware queue can still take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GetQueuedCmdLeftSpace;
+
+impl Command for GetQueuedCmdLeftSpace {
+    const ID: CommandID = CommandID::GetQueuedCmdLeftSpace;
+    const RW: bool = false;
+    const IS_QUEUED: bool = false;
+    type Response = u32;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
@@ -0,0 +1,77 @@
+//! Finding controllers connected to serial ports.
+//!
+//! Serial port paths like `/dev/ttyUSB0` change between reboots, so
+//! [Dobot::discover] lists the ports of the USB adapters Dobot controllers
+//! use and asks each for its serial number and name. Only those read-only
+//! requests are sent, so a port that belongs to another device is left alone
+//! apart from them, and a controller is not reset or reconfigured.
+use crate::{base::Dobot, error::Result as DobotResult, transport::Transport};
+use serialport::SerialPortType;
+use std::time::Duration;
+
+/// The USB vendor and product IDs of the CH340 and CP210x adapters.
+pub const USB_IDS: &[(u16, u16)] = &[(0x1a86, 0x7523), (0x10c4, 0xea60)];
+/// The time to wait for each probe request.
+pub const PROBE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// A controller found on a serial port.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DiscoveredDobot {
+    /// The path of the serial port, to be passed to [Dobot::open].
+    pub path: String,
+    /// The serial number of the controller.
+    pub serial: String,
+    /// The name of the controller.
+    pub name: String,
+}
+
+impl DiscoveredDobot {
+    /// Ask the controller on the transport for its serial number and name.
+    ///
+    /// The controller is not initialized, unlike in [Dobot::from_transport].
+    pub async fn probe<P, T>(path: P, transport: T) -> DobotResult<Self>
+    where
+        P: Into<String>,
+        T: Transport + 'static,
+    {
+        let mut dobot = Dobot::uninitialized(transport);
+        dobot.set_timeout(Some(PROBE_TIMEOUT));
+        let serial = dobot.get_device_sn().await?;
+        let name = dobot.get_device_name().await?;
+        Ok(Self {
+            path: path.into(),
+            serial,
+            name,
+        })
+    }
+}
+
+/// List the serial ports whose USB adapter is one Dobot controllers use.
+pub fn candidate_ports() -> DobotResult<Vec<String>> {
+    let ports = serialport::available_ports()?
+        .into_iter()
+        .filter(|port| match &port.port_type {
+            SerialPortType::UsbPort(usb) => USB_IDS.contains(&(usb.vid, usb.pid)),
+            _ => false,
+        })
+        .map(|port| port.port_name)
+        .collect();
+    Ok(ports)
+}
+
+/// Probe every candidate port, skipping those that cannot be opened or do not answer.
+#[cfg(any(feature = "tokio", all(feature = "async-io", unix)))]
+pub(crate) async fn discover() -> DobotResult<Vec<DiscoveredDobot>> {
+    let mut found = Vec::new();
+    for path in candidate_ports()? {
+        // a busy port or a silent device is not a controller we can use
+        let transport = match crate::transport::open_serial(&path) {
+            Ok(transport) => transport,
+            Err(_) => continue,
+        };
+        if let Ok(dobot) = DiscoveredDobot::probe(path, transport).await {
+            found.push(dobot);
+        }
+    }
+    Ok(found)
+}
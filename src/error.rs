@@ -1,34 +1,159 @@
-use failure::Fail;
+#[cfg(feature = "std")]
+use crate::alarm::Alarms;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+use core::fmt;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use serialport::Error as SerialError;
+#[cfg(feature = "std")]
 use std::io::Error as StdIoError;
-use tokio_serial::Error as TokioSerialError;
 
 /// Error type for dobot crate.
-#[derive(Debug, Fail)]
+///
+/// It implements [std::error::Error] and is `Send + Sync`, so it works with
+/// `?` into boxed errors or `anyhow`. Variants carrying strings need the
+/// `alloc` feature, those carrying IO errors or alarms need the `std`
+/// feature, and serial port errors need a runtime feature.
+#[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
-    #[fail(display = "the size of params can be up to 254 bytes")]
+    /// A message was built with more params than a frame can carry.
     ParamsTooLong,
-    #[fail(display = "fail to deserialize message: {}", _0)]
+    /// A response or path could not be turned into the requested value.
+    #[cfg(feature = "alloc")]
     DeserializeError(String),
-    #[fail(display = "io error: {:?}", _0)]
+    /// Received bytes do not form a valid frame.
+    MalformedMessage(&'static str),
+    /// The params ended before every field was read.
+    ParamsTruncated { needed: usize, remaining: usize },
+    /// The params had bytes left over after every field was read.
+    TrailingParams(usize),
+    /// The buffer passed to an encoder cannot hold the frame.
+    BufferTooSmall { needed: usize, available: usize },
+    /// Reading from or writing to the transport failed.
+    #[cfg(feature = "std")]
     IoError(StdIoError),
-    #[fail(
-        display = "checksum error: received {}, but it should be {}",
-        received, expected
-    )]
+    /// A received frame does not match its checksum.
     IntegrityError { received: u8, expected: u8 },
-    #[fail(display = "tokio-serial error: {}", _0)]
-    AsyncIOError(TokioSerialError),
+    /// No response or queue progress arrived within the configured time.
+    Timeout,
+    /// The response does not answer the request that was sent.
+    #[cfg(feature = "alloc")]
+    UnexpectedResponse { expected: String, got: String },
+    /// The task owning a shared controller has stopped.
+    Disconnected,
+    /// The queue was cleared before the awaited command was executed.
+    QueueCleared,
+    /// The executed queue index stopped advancing for the stall timeout.
+    Stalled { index: u64 },
+    /// The controller raised alarms while a command was awaited.
+    ///
+    /// Workspace alarms are reported as [Error::OutOfWorkspace] instead; use
+    /// [Error::alarms] to handle both.
+    #[cfg(feature = "std")]
+    Alarm(Alarms),
+    /// The controller could not plan or follow a motion to the target.
+    ///
+    /// Returned instead of [Error::Alarm] when the raised alarms include one
+    /// for which [Alarms::is_out_of_workspace] holds, since an unreachable
+    /// target is fixed by the caller rather than by clearing the arm.
+    #[cfg(feature = "std")]
+    OutOfWorkspace(Alarms),
+    /// An argument is not accepted by the command or the controller.
+    #[cfg(feature = "alloc")]
+    InvalidParameter(String),
+    /// No connected device matches the requested serial number or name.
+    #[cfg(feature = "alloc")]
+    DeviceNotFound(String),
+    /// The serial port could not be opened or configured.
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    AsyncIOError(SerialError),
 }
 
+impl Error {
+    /// Get the alarms raised by the controller, whichever variant reports them.
+    #[cfg(feature = "std")]
+    pub fn alarms(&self) -> Option<&Alarms> {
+        match self {
+            Self::Alarm(alarms) | Self::OutOfWorkspace(alarms) => Some(alarms),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ParamsTooLong => write!(f, "the size of params can be up to 253 bytes"),
+            #[cfg(feature = "alloc")]
+            Self::DeserializeError(reason) => write!(f, "fail to deserialize message: {}", reason),
+            Self::MalformedMessage(reason) => write!(f, "malformed message: {}", reason),
+            Self::ParamsTruncated { needed, remaining } => write!(
+                f,
+                "params are truncated: {} bytes needed, {} remaining",
+                needed, remaining
+            ),
+            Self::TrailingParams(len) => write!(f, "{} unexpected bytes after the params", len),
+            Self::BufferTooSmall { needed, available } => write!(
+                f,
+                "buffer too small: {} bytes needed, {} available",
+                needed, available
+            ),
+            #[cfg(feature = "std")]
+            Self::IoError(error) => write!(f, "io error: {}", error),
+            Self::IntegrityError { received, expected } => write!(
+                f,
+                "checksum error: received {}, but it should be {}",
+                received, expected
+            ),
+            Self::Timeout => write!(f, "operation timed out"),
+            #[cfg(feature = "alloc")]
+            Self::UnexpectedResponse { expected, got } => {
+                write!(f, "unexpected response: expected {}, got {}", expected, got)
+            }
+            Self::Disconnected => write!(f, "the connection to the controller is closed"),
+            Self::QueueCleared => write!(
+                f,
+                "the command queue was cleared before the command executed"
+            ),
+            Self::Stalled { index } => write!(f, "the command queue stalled at index {}", index),
+            #[cfg(feature = "std")]
+            Self::Alarm(alarms) => write!(f, "alarm raised: {}", alarms),
+            #[cfg(feature = "std")]
+            Self::OutOfWorkspace(alarms) => write!(f, "target is out of the workspace: {}", alarms),
+            #[cfg(feature = "alloc")]
+            Self::InvalidParameter(reason) => write!(f, "invalid parameter: {}", reason),
+            #[cfg(feature = "alloc")]
+            Self::DeviceNotFound(reason) => write!(f, "device not found: {}", reason),
+            #[cfg(any(feature = "tokio", feature = "async-io"))]
+            Self::AsyncIOError(error) => write!(f, "serial port error: {}", error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(error) => Some(error),
+            #[cfg(any(feature = "tokio", feature = "async-io"))]
+            Self::AsyncIOError(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 impl From<StdIoError> for Error {
     fn from(error: StdIoError) -> Self {
         Self::IoError(error)
     }
 }
-impl From<TokioSerialError> for Error {
-    fn from(error: TokioSerialError) -> Self {
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+impl From<SerialError> for Error {
+    fn from(error: SerialError) -> Self {
         Self::AsyncIOError(error)
     }
 }
 
-pub type Result<T> = std::result::Result<T, Error>;
+pub type Result<T> = core::result::Result<T, Error>;
@@ -0,0 +1,223 @@
+//! Shareable access to a Dobot owned by a background task.
+//!
+//! The task is spawned on tokio and fed through tokio channels, so this
+//! module is only built with the `tokio` feature. With `async-io`, share the
+//! [Dobot] behind an async mutex instead.
+use crate::{
+    base::{Dobot, Motion, Pose, QueuedCommand, Target},
+    command::Command,
+    error::{Error as DobotError, Result as DobotResult},
+    message::DobotMessage,
+    rt,
+    wait::{Progress, WaitOptions},
+};
+use std::{future::Future, pin::Pin};
+use tokio::sync::{mpsc, oneshot};
+
+type Job = Box<dyn for<'a> FnOnce(&'a mut Dobot) -> BoxFuture<'a, ()> + Send>;
+
+/// A boxed future borrowing the [Dobot] it runs on.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+const JOB_QUEUE_LEN: usize = 32;
+
+/// A cloneable handle to a [Dobot] owned by a connection task.
+///
+/// Operations from all clones are run one at a time by the task, so every
+/// exchange on the wire stays intact while many tasks poll the pose, read IO
+/// and submit commands concurrently. Waiting for queued commands is done
+/// with separate polls, so it never blocks other users of the handle.
+///
+/// The connection task stops once every handle is dropped.
+#[derive(Clone)]
+pub struct DobotHandle {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl DobotHandle {
+    /// Spawn the connection task owning the controller.
+    ///
+    /// Must be called within a tokio runtime, which keeps the task running
+    /// for as long as any handle exists.
+    pub fn spawn(mut dobot: Dobot) -> Self {
+        let (jobs, mut receiver) = mpsc::channel::<Job>(JOB_QUEUE_LEN);
+        tokio::spawn(async move {
+            while let Some(job) = receiver.recv().await {
+                job(&mut dobot).await;
+            }
+        });
+        Self { jobs }
+    }
+
+    /// Run an operation on the controller and obtain its result.
+    ///
+    /// ```no_run
+    /// # async fn example(handle: dobot_fx24::DobotHandle) -> dobot_fx24::error::Result<()> {
+    /// let pose = handle
+    ///     .run(|dobot| Box::pin(async move { dobot.get_pose().await }))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// The operation runs to completion even if the returned future is dropped.
+    pub async fn run<F, T>(&self, operation: F) -> DobotResult<T>
+    where
+        F: for<'a> FnOnce(&'a mut Dobot) -> BoxFuture<'a, DobotResult<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let job: Job = Box::new(move |dobot| {
+            Box::pin(async move {
+                let _ = sender.send(operation(dobot).await);
+            })
+        });
+        self.jobs
+            .send(job)
+            .await
+            .map_err(|_| DobotError::Disconnected)?;
+        receiver.await.map_err(|_| DobotError::Disconnected)?
+    }
+
+    /// Send user-defined request to Dobot and obtain response.
+    pub async fn send_command(&self, request_msg: DobotMessage) -> DobotResult<DobotMessage> {
+        self.run(|dobot| Box::pin(dobot.send_command(request_msg)))
+            .await
+    }
+
+    /// Send a typed command and decode its response.
+    pub async fn execute<C>(&self, command: C) -> DobotResult<C::Response>
+    where
+        C: Command + Send + Sync + 'static,
+        C::Response: Send + 'static,
+    {
+        self.run(move |dobot| Box::pin(async move { dobot.execute(&command).await }))
+            .await
+    }
+
+    /// Get the current pose of robot.
+    pub async fn get_pose(&self) -> DobotResult<Pose> {
+        self.run(|dobot| Box::pin(dobot.get_pose())).await
+    }
+
+    /// Get the index of the last executed queued command.
+    pub async fn get_queued_cmd_current_index(&self) -> DobotResult<u64> {
+        self.run(|dobot| Box::pin(dobot.get_queued_cmd_current_index()))
+            .await
+    }
+
+    /// Move to given pose and obtain a ticket for the queued command.
+    pub async fn move_to(&self, x: f32, y: f32, z: f32, r: f32) -> DobotResult<QueuedCommand> {
+        self.run(move |dobot| {
+            Box::pin(async move {
+                let handle = dobot.move_to(x, y, z, r).await?;
+                Ok(handle.detach())
+            })
+        })
+        .await
+    }
+
+    /// Move to given target with the selected motion and obtain a ticket for the queued command.
+    pub async fn move_to_target(
+        &self,
+        target: Target,
+        motion: Motion,
+    ) -> DobotResult<QueuedCommand> {
+        self.run(move |dobot| {
+            Box::pin(async move {
+                let handle = dobot.move_to_target(target, motion).await?;
+                Ok(handle.detach())
+            })
+        })
+        .await
+    }
+
+    /// Switch the suction cup and obtain a ticket for the queued command.
+    pub async fn set_end_effector_suction_cup(&self, enable: bool) -> DobotResult<QueuedCommand> {
+        self.run(move |dobot| {
+            Box::pin(async move {
+                let handle = dobot.set_end_effector_suction_cup(enable).await?;
+                Ok(handle.detach())
+            })
+        })
+        .await
+    }
+
+    /// Switch the gripper and obtain a ticket for the queued command.
+    pub async fn set_end_effector_gripper(&self, enable: bool) -> DobotResult<QueuedCommand> {
+        self.run(move |dobot| {
+            Box::pin(async move {
+                let handle = dobot.set_end_effector_gripper(enable).await?;
+                Ok(handle.detach())
+            })
+        })
+        .await
+    }
+
+    /// Wait until the queued command of the ticket is executed.
+    ///
+    /// The controller's [WaitOptions] apply. Each poll is a separate
+    /// operation, so other clones keep access to the controller in between.
+    pub async fn wait_for(&self, command: &QueuedCommand) -> DobotResult<()> {
+        let wait_options = self
+            .run(|dobot| Box::pin(async move { Ok(dobot.wait_options().clone()) }))
+            .await?;
+        self.wait_for_with(command, &wait_options).await
+    }
+
+    /// Wait until the queued command of the ticket is executed, polling as configured.
+    pub async fn wait_for_with(
+        &self,
+        command: &QueuedCommand,
+        wait_options: &WaitOptions,
+    ) -> DobotResult<()> {
+        let mut progress = Progress::new(wait_options);
+        loop {
+            let ticket = command.clone();
+            let current_index = self
+                .run(|dobot| Box::pin(async move { dobot.executed_index(&ticket).await }))
+                .await?;
+            let executed = progress.update(command.index, current_index);
+            if let Ok(true) = executed {
+                break Ok(());
+            }
+            if progress.alarm_check_due(executed.is_err()) {
+                let alarm_policy = wait_options.alarm_policy;
+                self.run(move |dobot| Box::pin(dobot.check_alarms(alarm_policy)))
+                    .await?;
+            }
+            executed?;
+            rt::sleep(wait_options.poll_interval).await;
+        }
+    }
+
+    /// Wait until every command queued so far is executed.
+    pub async fn wait_until_idle(&self) -> DobotResult<()> {
+        let last = self
+            .run(|dobot| {
+                Box::pin(
+                    async move { Ok(dobot.last_queued_index().map(|index| dobot.ticket(index))) },
+                )
+            })
+            .await?;
+        match last {
+            Some(command) => self.wait_for(&command).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Wait until the queued commands of all tickets are executed.
+    pub async fn wait_all(&self, commands: &[QueuedCommand]) -> DobotResult<()> {
+        match commands.iter().max_by_key(|command| command.index) {
+            Some(last) => self.wait_for(last).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Dobot {
+    /// Move the controller into a connection task and get a cloneable handle to it.
+    pub fn into_handle(self) -> DobotHandle {
+        DobotHandle::spawn(self)
+    }
+}
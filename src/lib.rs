@@ -1,6 +1,64 @@
 //! This crate provides high-level API to control Dobot robot arms.
+//!
+//! The controller talks over any `futures-io` stream, with the timers and
+//! serial port of the runtime picked by a feature:
+//!
+//! - `tokio` (default) also provides [DobotHandle], the blocking API and the
+//!   message codec.
+//! - `async-io` runs on async-std and smol.
+//!
+//! Without a runtime feature, only the [message] module and the [error] type
+//! are built. They are `no_std` unless the `std` feature is enabled, and the
+//! `alloc` feature adds the owned message types on top.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod alarm;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub mod base;
+#[cfg(feature = "tokio")]
+pub mod blocking;
+#[cfg(feature = "tokio")]
+pub mod codec;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub mod command;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub mod discovery;
 pub mod error;
+#[cfg(feature = "tokio")]
+pub mod handle;
 pub mod message;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub mod queue;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub mod retry;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod rt;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub mod sim;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub mod transport;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub mod wait;
 
-pub use base::{Dobot, Mode, Pose};
+#[cfg(feature = "std")]
+pub use alarm::{AlarmPolicy, Alarms};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use base::{Dobot, Mode, Motion, Pose, QueuedCommand, Target};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use command::{Command, Response};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use discovery::DiscoveredDobot;
+#[cfg(feature = "tokio")]
+pub use handle::DobotHandle;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use queue::CommandQueue;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use retry::RetryPolicy;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use transport::Transport;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use wait::WaitOptions;
@@ -1,13 +1,25 @@
-use crate::{
-    base::CommandID,
-    error::{Error as DobotError, Result as DobotResult},
-};
-use getset::{CopyGetters, Getters};
-use num_traits::FromPrimitive;
-use std::{convert::TryInto, io::prelude::*, marker::Unpin};
-use tokio::io::AsyncReadExt;
+use crate::error::{Error as DobotError, Result as DobotResult};
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+use core::convert::TryInto;
+#[cfg(feature = "std")]
+use futures_util::io::{AsyncRead, AsyncReadExt};
+use getset::CopyGetters;
+#[cfg(feature = "alloc")]
+use getset::Getters;
+#[cfg(feature = "std")]
+use std::{io::prelude::*, marker::Unpin};
+
+/// The two bytes every message starts with.
+pub const HEADER: [u8; 2] = [0xaa, 0xaa];
+/// The largest params size that fits in the length byte.
+pub const MAX_PARAMS_LEN: usize = u8::MAX as usize - 2;
+/// The size of the largest serialized message.
+pub const MAX_FRAME_LEN: usize = MAX_PARAMS_LEN + 6;
 
 /// The message format of Dobot protocol.
+#[cfg(feature = "alloc")]
+#[allow(clippy::len_without_is_empty)]
 #[derive(Clone, Debug, Getters, CopyGetters)]
 pub struct DobotMessage {
     #[get = "pub"]
@@ -26,18 +38,19 @@ pub struct DobotMessage {
     checksum: u8,
 }
 
+#[cfg(feature = "alloc")]
 impl DobotMessage {
     /// Create message object.
     pub fn new(id: CommandID, rw: bool, is_queued: bool, params: Vec<u8>) -> DobotResult<Self> {
-        if params.len() > u8::max_value() as usize + 2 {
+        if params.len() > MAX_PARAMS_LEN {
             return Err(DobotError::ParamsTooLong);
         }
 
         let len = params.len() as u8 + 2;
-        let checksum = Self::compute_checksum(id, rw, is_queued, &params);
+        let checksum = compute_checksum(id, rw, is_queued, &params);
 
         let msg = Self {
-            header: [0xaa, 0xaa],
+            header: HEADER,
             len,
             id,
             rw,
@@ -49,18 +62,24 @@ impl DobotMessage {
         Ok(msg)
     }
 
+    /// Get the number of bytes of the serialized message.
+    pub fn encoded_len(&self) -> usize {
+        self.params.len() + 6
+    }
+
     /// Serialize message to bytes.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let ctrl = ((self.is_queued as u8) << 1) | (self.rw as u8);
-        self.header
-            .iter()
-            .chain([self.len].iter())
-            .chain([self.id as u8].iter())
-            .chain([ctrl].iter())
-            .chain(self.params.iter())
-            .chain([self.checksum].iter())
-            .map(|byte| *byte)
-            .collect::<Vec<u8>>()
+        let mut bytes = vec![0u8; self.encoded_len()];
+        self.encode_into(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// Serialize message into the front of a buffer without allocating.
+    ///
+    /// Returns the number of bytes written. A buffer of [MAX_FRAME_LEN] bytes
+    /// fits any message.
+    pub fn encode_into(&self, buffer: &mut [u8]) -> DobotResult<usize> {
+        self.as_message_ref().encode_into(buffer)
     }
 
     /// Create message from serialized bytes.
@@ -68,117 +87,764 @@ impl DobotMessage {
     where
         B: AsRef<[u8]>,
     {
-        let as_ref = bytes.as_ref();
-        if as_ref.len() < 6 {
-            return Err(DobotError::DeserializeError("message is truncated".into()));
+        DobotMessageRef::from_bytes(bytes.as_ref()).map(|msg| msg.to_message())
+    }
+
+    /// Borrow the message as a [DobotMessageRef].
+    pub fn as_message_ref(&self) -> DobotMessageRef<'_> {
+        DobotMessageRef {
+            len: self.len,
+            id: self.id,
+            rw: self.rw,
+            is_queued: self.is_queued,
+            params: &self.params,
+            checksum: self.checksum,
+        }
+    }
+
+    /// Create message by synchronously reading bytes from reader.
+    ///
+    /// Bytes in front of the message header are skipped.
+    #[cfg(feature = "std")]
+    pub fn from_reader<R>(mut reader: R) -> DobotResult<Self>
+    where
+        R: Read,
+    {
+        let mut decoder = MessageDecoder::new();
+        let mut bytes = [0u8; MAX_FRAME_LEN];
+        loop {
+            let bytes = &mut bytes[..decoder.bytes_needed()];
+            reader.read_exact(bytes)?;
+            decoder.extend(bytes);
+            if let Some(result) = decoder.decode() {
+                break result;
+            }
+        }
+    }
+
+    /// Create message by asynchronously reading bytes from reader.
+    ///
+    /// Bytes in front of the message header are skipped.
+    #[cfg(feature = "std")]
+    pub async fn from_async_reader<R>(mut reader: R) -> DobotResult<Self>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let mut decoder = MessageDecoder::new();
+        let mut bytes = [0u8; MAX_FRAME_LEN];
+        loop {
+            let bytes = &mut bytes[..decoder.bytes_needed()];
+            reader.read_exact(bytes).await?;
+            decoder.extend(bytes);
+            if let Some(result) = decoder.decode() {
+                break result;
+            }
+        }
+    }
+}
+
+/// A message borrowing its params from a buffer.
+///
+/// Decoding into it copies nothing, which suits streaming at a high rate.
+/// Use [DobotMessageRef::to_message] to keep the message.
+#[allow(clippy::len_without_is_empty)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, CopyGetters)]
+pub struct DobotMessageRef<'a> {
+    #[get_copy = "pub"]
+    len: u8,
+    #[get_copy = "pub"]
+    id: CommandID,
+    #[get_copy = "pub"]
+    rw: bool,
+    #[get_copy = "pub"]
+    is_queued: bool,
+    #[get_copy = "pub"]
+    params: &'a [u8],
+    #[get_copy = "pub"]
+    checksum: u8,
+}
+
+impl<'a> DobotMessageRef<'a> {
+    /// Create message borrowing the given params.
+    pub fn new(id: CommandID, rw: bool, is_queued: bool, params: &'a [u8]) -> DobotResult<Self> {
+        if params.len() > MAX_PARAMS_LEN {
+            return Err(DobotError::ParamsTooLong);
         }
 
-        let header: [u8; 2] = as_ref[0..2].try_into().unwrap();
-        let len = as_ref[2];
+        Ok(Self {
+            len: params.len() as u8 + 2,
+            id,
+            rw,
+            is_queued,
+            params,
+            checksum: compute_checksum(id, rw, is_queued, params),
+        })
+    }
+
+    /// Get the number of bytes of the serialized message.
+    pub fn encoded_len(&self) -> usize {
+        self.params.len() + 6
+    }
+
+    /// Serialize message into the front of a buffer.
+    ///
+    /// Returns the number of bytes written. A buffer of [MAX_FRAME_LEN] bytes
+    /// fits any message.
+    pub fn encode_into(&self, buffer: &mut [u8]) -> DobotResult<usize> {
+        let len = self.encoded_len();
+        if buffer.len() < len {
+            return Err(DobotError::BufferTooSmall {
+                needed: len,
+                available: buffer.len(),
+            });
+        }
+
+        buffer[0..2].copy_from_slice(&HEADER);
+        buffer[2] = self.len;
+        buffer[3] = u8::from(self.id);
+        buffer[4] = ((self.is_queued as u8) << 1) | (self.rw as u8);
+        buffer[5..(len - 1)].copy_from_slice(self.params);
+        buffer[len - 1] = self.checksum;
+        Ok(len)
+    }
+
+    /// Parse a message from serialized bytes without copying the params.
+    pub fn from_bytes(bytes: &'a [u8]) -> DobotResult<Self> {
+        if bytes.len() < 6 {
+            return Err(DobotError::MalformedMessage("message is truncated"));
+        }
+        if bytes[0..2] != HEADER {
+            return Err(DobotError::MalformedMessage("missing message header"));
+        }
 
-        if as_ref.len() != len as usize + 4 {
-            return Err(DobotError::DeserializeError("message is truncated".into()));
+        let len = bytes[2];
+        if len < 2 || bytes.len() != len as usize + 4 {
+            return Err(DobotError::MalformedMessage("message is truncated"));
         }
 
-        let id = CommandID::from_u8(as_ref[3]).ok_or(DobotError::DeserializeError(format!(
-            "unrecognized command ID {}",
-            as_ref[3]
-        )))?;
-        let ctrl = as_ref[4];
+        let id = CommandID::from(bytes[3]);
+        let ctrl = bytes[4];
         let rw = (ctrl & 0x01) != 0;
         let is_queued = (ctrl & 0x02) != 0;
-        let params = as_ref[5..(as_ref.len() - 1)]
-            .into_iter()
-            .map(|byte| *byte)
-            .collect::<Vec<u8>>();
-        let checksum = as_ref[as_ref.len() - 1];
-
-        {
-            let expected = Self::compute_checksum(id, rw, is_queued, &params);
-            if expected != checksum {
-                return Err(DobotError::IntegrityError {
-                    expected,
-                    received: checksum,
-                });
-            }
+        let params = &bytes[5..(bytes.len() - 1)];
+        let checksum = bytes[bytes.len() - 1];
+
+        let expected = compute_checksum(id, rw, is_queued, params);
+        if expected != checksum {
+            return Err(DobotError::IntegrityError {
+                expected,
+                received: checksum,
+            });
         }
 
-        let msg = Self {
-            header,
+        Ok(Self {
             len,
             id,
             rw,
             is_queued,
             params,
             checksum,
-        };
+        })
+    }
 
-        Ok(msg)
+    /// Copy the message into an owned [DobotMessage].
+    #[cfg(feature = "alloc")]
+    pub fn to_message(&self) -> DobotMessage {
+        DobotMessage {
+            header: HEADER,
+            len: self.len,
+            id: self.id,
+            rw: self.rw,
+            is_queued: self.is_queued,
+            params: self.params.to_vec(),
+            checksum: self.checksum,
+        }
     }
+}
 
-    /// Create message by synchronously reading bytes from reader.
-    pub fn from_reader<R>(mut reader: R) -> DobotResult<Self>
+fn compute_checksum(id: CommandID, rw: bool, is_queued: bool, params: &[u8]) -> u8 {
+    let ctrl = ((is_queued as u8) << 1) | (rw as u8);
+    let (checksum, _) = u8::from(id).overflowing_add(ctrl);
+    let (checksum, _) = params
+        .iter()
+        .fold(0u8, |prev_cksum, byte| {
+            let (new_cksum, _) = prev_cksum.overflowing_add(*byte);
+            new_cksum
+        })
+        .overflowing_add(checksum);
+    let (checksum, _) = checksum.overflowing_neg();
+    checksum
+}
+
+/// What the front of a byte stream holds.
+#[cfg(feature = "alloc")]
+pub(crate) enum Scan {
+    /// Bytes to skip before the next possible header.
+    Garbage(usize),
+    /// More bytes are needed.
+    Incomplete,
+    /// A frame of the given length, whose checksum is not checked yet.
+    Frame(usize),
+}
+
+/// Look for the next frame at the front of the bytes.
+#[cfg(feature = "alloc")]
+pub(crate) fn scan(bytes: &[u8]) -> Scan {
+    let start = match bytes.windows(2).position(|window| window == HEADER) {
+        Some(start) => start,
+        // keep a trailing byte that may begin the next header
+        None if bytes.last() == Some(&HEADER[0]) => bytes.len() - 1,
+        None => bytes.len(),
+    };
+    if start > 0 {
+        return Scan::Garbage(start);
+    }
+
+    if bytes.len() < 3 {
+        return Scan::Incomplete;
+    }
+    let len = bytes[2] as usize;
+    if len < 2 {
+        // a message carries at least the ID and control bytes
+        return Scan::Garbage(1);
+    }
+    if bytes.len() < len + 4 {
+        return Scan::Incomplete;
+    }
+    Scan::Frame(len + 4)
+}
+
+#[cfg(feature = "alloc")]
+/// Incremental decoder splitting a byte stream into messages.
+///
+/// The decoder scans for the `0xAA 0xAA` header and skips the garbage in
+/// front of it. Frames with an impossible length or a wrong checksum are
+/// dropped one byte at a time, so the stream resynchronizes on the next
+/// valid header after any byte is lost or corrupted.
+///
+/// The buffer is reused for the whole stream, and [MessageDecoder::decode_ref]
+/// hands out messages borrowing from it.
+#[derive(Clone, Debug, Default)]
+pub struct MessageDecoder {
+    buffer: Vec<u8>,
+    start: usize,
+    bytes_discarded: u64,
+}
+
+#[cfg(feature = "alloc")]
+impl MessageDecoder {
+    /// Create decoder with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append received bytes to the buffer.
+    pub fn extend(&mut self, bytes: &[u8]) {
+        if self.start > 0 {
+            // move the undecoded bytes to the front instead of growing the buffer
+            self.buffer.drain(..self.start);
+            self.start = 0;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Take the next message out of the buffer.
+    ///
+    /// Returns `None` if more bytes are needed. A corrupted frame is reported
+    /// as an error once and skipped afterwards.
+    pub fn decode(&mut self) -> Option<DobotResult<DobotMessage>> {
+        self.decode_ref()
+            .map(|result| result.map(|msg| msg.to_message()))
+    }
+
+    /// Take the next message out of the buffer without copying it.
+    ///
+    /// Behaves like [MessageDecoder::decode], but the message borrows from
+    /// the buffer until the decoder is used again.
+    pub fn decode_ref(&mut self) -> Option<DobotResult<DobotMessageRef<'_>>> {
+        loop {
+            let len = match scan(self.pending()) {
+                Scan::Garbage(count) => {
+                    self.discard(count);
+                    continue;
+                }
+                Scan::Incomplete => return None,
+                Scan::Frame(len) => len,
+            };
+
+            // fields are borrowed separately, so the message can borrow the buffer
+            let frame = self.start..(self.start + len);
+            let result = DobotMessageRef::from_bytes(&self.buffer[frame.clone()]);
+            match result {
+                Ok(_) => self.start = frame.end,
+                Err(_) => {
+                    self.start += 1;
+                    self.bytes_discarded += 1;
+                }
+            }
+            return Some(result);
+        }
+    }
+
+    /// Get the number of bytes the next message needs at least.
+    pub fn bytes_needed(&self) -> usize {
+        let pending = self.pending();
+        match pending.get(..3) {
+            Some([0xaa, 0xaa, len]) if *len >= 2 => {
+                (*len as usize + 4).saturating_sub(pending.len()).max(1)
+            }
+            _ => 1,
+        }
+    }
+
+    /// Get the number of buffered bytes not decoded yet.
+    pub fn buffered_len(&self) -> usize {
+        self.pending().len()
+    }
+
+    /// Get the total number of bytes skipped while searching for messages.
+    pub fn bytes_discarded(&self) -> u64 {
+        self.bytes_discarded
+    }
+
+    /// Drop all buffered bytes.
+    pub fn clear(&mut self) {
+        self.discard(self.pending().len());
+    }
+
+    fn pending(&self) -> &[u8] {
+        &self.buffer[self.start..]
+    }
+
+    fn discard(&mut self, count: usize) {
+        self.start += count;
+        self.bytes_discarded += count as u64;
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Serializes the little-endian fields of message params.
+///
+/// ```
+/// use dobot_fx24::message::ParamsWriter;
+///
+/// let params = ParamsWriter::new().u8(0x01).f32(200.0).finish();
+/// assert_eq!(params, [0x01, 0x00, 0x00, 0x48, 0x43]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParamsWriter {
+    params: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl ParamsWriter {
+    /// Create writer with empty params.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a byte.
+    pub fn u8(mut self, value: u8) -> Self {
+        self.params.push(value);
+        self
+    }
+
+    /// Append a boolean as a single byte.
+    pub fn bool(self, value: bool) -> Self {
+        self.u8(value as u8)
+    }
+
+    /// Append a little-endian `u32`.
+    pub fn u32(self, value: u32) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    /// Append a little-endian `u64`.
+    pub fn u64(self, value: u64) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    /// Append a little-endian `f32`.
+    pub fn f32(self, value: f32) -> Self {
+        self.bytes(&value.to_le_bytes())
+    }
+
+    /// Append several little-endian `f32`s.
+    pub fn f32s(self, values: &[f32]) -> Self {
+        values.iter().fold(self, |writer, value| writer.f32(*value))
+    }
+
+    /// Append raw bytes.
+    pub fn bytes(mut self, bytes: &[u8]) -> Self {
+        self.params.extend_from_slice(bytes);
+        self
+    }
+
+    /// Append a field of a protocol struct.
+    pub fn params<P>(self, value: &P) -> Self
     where
-        R: Read,
+        P: Params,
     {
-        let header_buffer = {
-            let mut header = [0; 2];
-            reader.read_exact(&mut header)?;
-            header
-        };
-        let len_buffer = {
-            let mut len = [0; 1];
-            reader.read_exact(&mut len)?;
-            len
-        };
-        let len = len_buffer[0];
-        let data_buffer = {
-            let mut data = vec![0; len as usize];
-            reader.read_exact(&mut data)?;
-            data
-        };
-        let bytes = [
-            header_buffer.as_slice(),
-            len_buffer.as_slice(),
-            data_buffer.as_slice(),
-        ]
-        .concat();
-        let msg = Self::from_bytes(bytes)?;
-        Ok(msg)
+        value.write(self)
     }
 
-    /// Create message by asynchronously reading bytes from reader.
-    pub async fn from_async_reader<R>(mut reader: R) -> DobotResult<Self>
+    /// Get the serialized params.
+    pub fn finish(self) -> Vec<u8> {
+        self.params
+    }
+}
+
+/// Deserializes the little-endian fields of message params.
+///
+/// Reading past the end fails with [DobotError::ParamsTruncated] instead of
+/// panicking.
+#[derive(Clone, Debug)]
+pub struct ParamsReader<'a> {
+    params: &'a [u8],
+}
+
+impl<'a> ParamsReader<'a> {
+    /// Create reader over the given params.
+    pub fn new(params: &'a [u8]) -> Self {
+        Self { params }
+    }
+
+    /// Take the next `len` raw bytes.
+    pub fn bytes(&mut self, len: usize) -> DobotResult<&'a [u8]> {
+        if self.params.len() < len {
+            return Err(DobotError::ParamsTruncated {
+                needed: len,
+                remaining: self.params.len(),
+            });
+        }
+        let (head, tail) = self.params.split_at(len);
+        self.params = tail;
+        Ok(head)
+    }
+
+    /// Read a byte.
+    pub fn u8(&mut self) -> DobotResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Read a single-byte boolean.
+    pub fn bool(&mut self) -> DobotResult<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    /// Read a little-endian `u32`.
+    pub fn u32(&mut self) -> DobotResult<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Read a little-endian `u64`.
+    pub fn u64(&mut self) -> DobotResult<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// Read a little-endian `f32`.
+    pub fn f32(&mut self) -> DobotResult<f32> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Read several little-endian `f32`s.
+    pub fn f32s<const N: usize>(&mut self) -> DobotResult<[f32; N]> {
+        let mut values = [0.0; N];
+        for value in values.iter_mut() {
+            *value = self.f32()?;
+        }
+        Ok(values)
+    }
+
+    /// Read a field of a protocol struct.
+    #[cfg(feature = "alloc")]
+    pub fn params<P>(&mut self) -> DobotResult<P>
     where
-        R: AsyncReadExt + Unpin,
+        P: Params,
     {
-        let prefix = {
-            let mut prefix = [0u8; 5];
-            reader.read_exact(&mut prefix).await?;
-            prefix
-        };
-        let len = prefix[2];
-        let suffix = {
-            let mut suffix = vec![0u8; len as usize - 1];
-            reader.read_exact(suffix.as_mut_slice()).await?;
-            suffix
-        };
-        let bytes = [prefix.as_slice(), suffix.as_slice()].concat();
-        let msg = Self::from_bytes(bytes)?;
-        Ok(msg)
+        P::read(self)
+    }
+
+    /// Get the number of bytes not read yet.
+    pub fn remaining(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Check that every byte was read.
+    pub fn finish(self) -> DobotResult<()> {
+        match self.params.len() {
+            0 => Ok(()),
+            len => Err(DobotError::TrailingParams(len)),
+        }
+    }
+}
+
+/// A value serialized as a sequence of protocol fields.
+///
+/// Structs of fields implement it with [protocol_params](crate::protocol_params).
+#[cfg(feature = "alloc")]
+pub trait Params: Sized {
+    /// Append the fields to the writer.
+    fn write(&self, writer: ParamsWriter) -> ParamsWriter;
+
+    /// Read the fields from the reader.
+    fn read(reader: &mut ParamsReader) -> DobotResult<Self>;
+}
+
+#[cfg(feature = "alloc")]
+impl Params for u8 {
+    fn write(&self, writer: ParamsWriter) -> ParamsWriter {
+        writer.u8(*self)
+    }
+
+    fn read(reader: &mut ParamsReader) -> DobotResult<Self> {
+        reader.u8()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Params for bool {
+    fn write(&self, writer: ParamsWriter) -> ParamsWriter {
+        writer.bool(*self)
+    }
+
+    fn read(reader: &mut ParamsReader) -> DobotResult<Self> {
+        reader.bool()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Params for u32 {
+    fn write(&self, writer: ParamsWriter) -> ParamsWriter {
+        writer.u32(*self)
+    }
+
+    fn read(reader: &mut ParamsReader) -> DobotResult<Self> {
+        reader.u32()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Params for u64 {
+    fn write(&self, writer: ParamsWriter) -> ParamsWriter {
+        writer.u64(*self)
+    }
+
+    fn read(reader: &mut ParamsReader) -> DobotResult<Self> {
+        reader.u64()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Params for f32 {
+    fn write(&self, writer: ParamsWriter) -> ParamsWriter {
+        writer.f32(*self)
+    }
+
+    fn read(reader: &mut ParamsReader) -> DobotResult<Self> {
+        reader.f32()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<const N: usize> Params for [f32; N] {
+    fn write(&self, writer: ParamsWriter) -> ParamsWriter {
+        writer.f32s(self)
     }
 
-    fn compute_checksum(id: CommandID, rw: bool, is_queued: bool, params: &[u8]) -> u8 {
-        let ctrl = ((is_queued as u8) << 1) | (rw as u8);
-        let (checksum, _) = (id as u8).overflowing_add(ctrl);
-        let (checksum, _) = params
-            .iter()
-            .fold(0u8, |prev_cksum, byte| {
-                let (new_cksum, _) = prev_cksum.overflowing_add(*byte);
-                new_cksum
-            })
-            .overflowing_add(checksum);
-        let (checksum, _) = checksum.overflowing_neg();
-        checksum
+    fn read(reader: &mut ParamsReader) -> DobotResult<Self> {
+        reader.f32s()
     }
 }
+
+/// Define a struct of protocol fields, serialized in declaration order.
+///
+/// The struct implements [Params], so it can be written with
+/// [ParamsWriter::params] and read with [ParamsReader::params]. With a
+/// runtime feature, it also implements [Response](crate::command::Response)
+/// and can be used as the response of a [Command](crate::command::Command).
+///
+/// ```
+/// use dobot_fx24::{
+///     message::{ParamsReader, ParamsWriter},
+///     protocol_params,
+/// };
+///
+/// protocol_params! {
+///     /// Parameters of the jump motion.
+///     #[derive(Debug, PartialEq)]
+///     pub struct JumpParams {
+///         pub jump_height: f32,
+///         pub z_limit: f32,
+///     }
+/// }
+///
+/// let params = ParamsWriter::new().f32s(&[20.0, 100.0]).finish();
+/// let decoded: JumpParams = ParamsReader::new(&params).params().unwrap();
+/// assert_eq!(decoded, JumpParams { jump_height: 20.0, z_limit: 100.0 });
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! protocol_params {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $field_vis:vis $field:ident: $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* $field_vis $field: $ty,)*
+        }
+
+        impl $crate::message::Params for $name {
+            fn write(&self, writer: $crate::message::ParamsWriter) -> $crate::message::ParamsWriter {
+                writer$(.params(&self.$field))*
+            }
+
+            fn read(
+                reader: &mut $crate::message::ParamsReader,
+            ) -> $crate::error::Result<Self> {
+                Ok(Self {
+                    $($field: reader.params()?,)*
+                })
+            }
+        }
+
+        $crate::__protocol_response!($name);
+    };
+}
+
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __protocol_response {
+    ($name:ident) => {
+        impl $crate::command::Response for $name {
+            fn decode(params: &[u8]) -> $crate::error::Result<Self> {
+                $crate::command::read_response(params, |reader| reader.params())
+            }
+        }
+    };
+}
+
+// the command module needs a runtime, so there is no response to implement
+#[cfg(not(any(feature = "tokio", feature = "async-io")))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __protocol_response {
+    ($name:ident) => {};
+}
+
+macro_rules! command_ids {
+    ($($name:ident = $value:literal,)*) => {
+        /// The ID of each command in Dobot protocol.
+        ///
+        /// IDs the crate does not know yet decode to [CommandID::Unknown], so
+        /// any frame can be received and new firmware commands can be sent
+        /// with `Dobot::send_command`.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum CommandID {
+            $($name,)*
+            /// An ID not listed above.
+            Unknown(u8),
+        }
+
+        impl From<u8> for CommandID {
+            fn from(id: u8) -> Self {
+                match id {
+                    $($value => CommandID::$name,)*
+                    id => CommandID::Unknown(id),
+                }
+            }
+        }
+
+        impl From<CommandID> for u8 {
+            fn from(id: CommandID) -> Self {
+                match id {
+                    $(CommandID::$name => $value,)*
+                    CommandID::Unknown(id) => id,
+                }
+            }
+        }
+    };
+}
+
+command_ids! {
+    GetSetDeviceSn = 0,
+    GetSetDeviceName = 1,
+    GetDeviceVersion = 2,
+    GetDeviceWithL = 3,
+    GetDeviceTime = 4,
+    GetDeviceId = 5,
+    GetPose = 10,
+    ResetPose = 11,
+    GetPoseL = 13,
+    GetAlarmsState = 20,
+    ClearAllAlarmsState = 21,
+    GetSetHomeParams = 30,
+    SetHomeCmd = 31,
+    GetSetAutoLeveling = 32,
+    GetSetHHTTrigMode = 40,
+    GetSetHHTTrigOutputEnabled = 41,
+    GetHHTTrigOutput = 42,
+    GetSetArmOrientation = 50,
+    GetSetEndEffectorParams = 60,
+    GetSetEndEffectorLaser = 61,
+    GetSetEndEffectorSuctionCup = 62,
+    GetSetEndEffectorGripper = 63,
+    GetSetJogJointParams = 70,
+    GetSetJogCoordinateParams = 71,
+    GetSetJogCommonParams = 72,
+    SetJogCmd = 73,
+    GetSetJogLParams = 74,
+    GetSetPtpJointParams = 80,
+    GetSetPtpCoordinateParams = 81,
+    GetSetPtpJumpParams = 82,
+    GetSetPtpCommonParams = 83,
+    SetPtpCmd = 84,
+    GetSetPtpLParams = 85,
+    SetPtpWithLCmd = 86,
+    GetSetPtpJump2Params = 87,
+    SetPtpPoCmd = 88,
+    SetPtpPoWithLCmd = 89,
+    GetSetCpParams = 90,
+    SetCpCmd = 91,
+    SetCpLeCmd = 92,
+    GetSetArcParams = 100,
+    SetSetArcCmd = 101,
+    SetCircleCmd = 102,
+    SetWaitCmd = 110,
+    SetTrigCmd = 120,
+    GetSetIoMultiplexing = 130,
+    GetSetIoDo = 131,
+    GetSetIoPwm = 132,
+    GetIoDi = 133,
+    GetIoAdc = 134,
+    SetEMotor = 135,
+    SetEMotorS = 136,
+    GetSetColorSensor = 137,
+    GetSetIrSwitch = 138,
+    GetSetAngleSensorStaticError = 140,
+    GetSetAngleSensorCoef = 141,
+    GetSetBaseDecoderStaticError = 142,
+    GetSetWifiConfigMode = 150,
+    GetSetWifiSsid = 151,
+    GetSetWifiPassword = 152,
+    GetSetWifiAddress = 153,
+    GetSetWifiNetmask = 154,
+    GetSetWifiGateway = 155,
+    GetSetWifiDns = 156,
+    GetSetWifiConnectStatus = 157,
+    SetLostStepParams = 170,
+    SetLostStepCmd = 171,
+    SetQueuedCmdStartExec = 240,
+    SetQueuedCmdStopExec = 241,
+    SetQueuedCmdForceStopExec = 242,
+    SetQueuedCmdStartDownload = 243,
+    SetQueuedCmdStopDownload = 244,
+    SetQueuedCmdClear = 245,
+    SetQueuedCmdCurrentIndex = 246,
+    GetQueuedCmdLeftSpace = 247,
+    GetQueuedCmdMotionFinish = 248,
+}
@@ -0,0 +1,180 @@
+//! Host-side buffering of queued commands.
+use crate::{
+    base::{queued_index, Dobot, QueuedCommand},
+    command::Command,
+    error::{Error as DobotError, Result as DobotResult},
+    message::DobotMessage,
+    rt,
+};
+use std::collections::VecDeque;
+
+/// Counts the commands of a [CommandQueue] at each stage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct QueueProgress {
+    /// Number of commands submitted to the host-side queue.
+    pub submitted: usize,
+    /// Number of commands sent to the firmware queue.
+    pub sent: usize,
+    /// Number of commands executed by the firmware.
+    pub executed: usize,
+    /// Number of sent commands a queue clear dropped before they were executed.
+    pub cleared: usize,
+}
+
+impl QueueProgress {
+    /// Check whether every submitted command is executed or cleared.
+    pub fn is_done(&self) -> bool {
+        self.executed + self.cleared == self.submitted
+    }
+}
+
+/// Buffers queued commands on the host and feeds them to the firmware queue.
+///
+/// The firmware queue only holds a few dozen commands, so any number of
+/// commands can be submitted here and they are sent as space frees up.
+///
+/// A command whose send fails stays pending and is sent again by the next
+/// feed. If the response was lost, it may have reached the queue already;
+/// see [Dobot::reconcile_queued].
+#[derive(Debug, Default)]
+pub struct CommandQueue {
+    pending: VecDeque<DobotMessage>,
+    sent: VecDeque<QueuedCommand>,
+    progress: QueueProgress,
+}
+
+impl CommandQueue {
+    /// Create empty queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Submit a queued command to be sent once the firmware queue has space.
+    pub fn push(&mut self, request_msg: DobotMessage) -> DobotResult<()> {
+        if !request_msg.is_queued() {
+            return Err(DobotError::InvalidParameter(format!(
+                "{:?} is not a queued command",
+                request_msg.id()
+            )));
+        }
+        self.pending.push_back(request_msg);
+        self.progress.submitted += 1;
+        Ok(())
+    }
+
+    /// Submit a typed queued command.
+    pub fn push_command<C>(&mut self, command: &C) -> DobotResult<()>
+    where
+        C: Command,
+    {
+        self.push(command.to_message()?)
+    }
+
+    /// Submit several queued commands.
+    pub fn extend<I>(&mut self, request_msgs: I) -> DobotResult<()>
+    where
+        I: IntoIterator<Item = DobotMessage>,
+    {
+        request_msgs
+            .into_iter()
+            .try_for_each(|request_msg| self.push(request_msg))
+    }
+
+    /// Get the number of commands not sent yet.
+    pub fn pending_len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Get the number of commands at each stage.
+    pub fn progress(&self) -> QueueProgress {
+        self.progress
+    }
+
+    /// Send as many pending commands as the firmware queue has space for.
+    ///
+    /// Returns the number of commands sent.
+    pub async fn feed(&mut self, dobot: &mut Dobot) -> DobotResult<usize> {
+        self.update(dobot).await?;
+        if self.pending.is_empty() {
+            return Ok(0);
+        }
+
+        let left_space = dobot.get_queued_cmd_left_space().await? as usize;
+        let mut count = 0;
+        while count < left_space {
+            let request_msg = match self.pending.front() {
+                Some(request_msg) => request_msg.clone(),
+                None => break,
+            };
+            let response_msg = dobot.send_command(request_msg).await?;
+            self.pending.pop_front();
+            self.sent
+                .push_back(dobot.ticket(queued_index(&response_msg)?));
+            self.progress.sent += 1;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Feed all pending commands and wait until they are executed.
+    pub async fn run(&mut self, dobot: &mut Dobot) -> DobotResult<()> {
+        self.run_with_progress(dobot, |_| {}).await
+    }
+
+    /// Feed all pending commands and wait until they are executed, reporting progress.
+    ///
+    /// While the firmware queue is full, the oldest sent command is waited
+    /// for with the controller's [WaitOptions](crate::WaitOptions), so an
+    /// alarm or a stalled queue ends the run with an error.
+    pub async fn run_with_progress<F>(
+        &mut self,
+        dobot: &mut Dobot,
+        mut on_progress: F,
+    ) -> DobotResult<()>
+    where
+        F: FnMut(QueueProgress),
+    {
+        loop {
+            self.feed(dobot).await?;
+            on_progress(self.progress);
+
+            let next = if self.pending.is_empty() {
+                self.sent.back()
+            } else {
+                self.sent.front()
+            };
+            match next.cloned() {
+                Some(command) => dobot.wait_for(&command).await?,
+                None if self.pending.is_empty() => break Ok(()),
+                // the firmware queue is filled by commands sent elsewhere
+                None => rt::sleep(dobot.wait_options().poll_interval).await,
+            }
+        }
+    }
+
+    /// Count the sent commands the firmware has executed since the last update.
+    async fn update(&mut self, dobot: &mut Dobot) -> DobotResult<()> {
+        // a queue clear drops the commands sent before it
+        while let Some(command) = self.sent.front() {
+            if dobot.is_current(command) {
+                break;
+            }
+            self.sent.pop_front();
+            self.progress.cleared += 1;
+        }
+
+        let oldest = match self.sent.front() {
+            Some(oldest) => oldest.clone(),
+            None => return Ok(()),
+        };
+        let current_index = dobot.executed_index(&oldest).await?;
+        while let Some(command) = self.sent.front() {
+            if command.index > current_index {
+                break;
+            }
+            self.sent.pop_front();
+            self.progress.executed += 1;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+//! Policies for sending failed requests again.
+use crate::{error::Error as DobotError, message::DobotMessage};
+use std::time::Duration;
+
+/// Decides whether a failed request is sent again.
+///
+/// Only idempotent requests, i.e. reads that do not enter the command queue,
+/// are retried, and only after a timeout or a checksum error. A queued
+/// command is never sent twice, since the first attempt may have reached the
+/// queue; see [Dobot::reconcile_queued](crate::Dobot::reconcile_queued).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RetryPolicy {
+    /// Number of attempts after the first one.
+    pub max_retries: u32,
+    /// Pause before each retry.
+    pub delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Create policy retrying up to `max_retries` times without delay.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            delay: Duration::from_millis(0),
+        }
+    }
+
+    /// Set the pause before each retry.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Check whether the request may be sent again after the error.
+    pub fn allows(&self, request_msg: &DobotMessage, error: &DobotError, retries: u32) -> bool {
+        let idempotent = !request_msg.is_queued() && !request_msg.rw();
+        let transient = matches!(
+            error,
+            DobotError::Timeout | DobotError::IntegrityError { .. }
+        );
+        idempotent && transient && retries < self.max_retries
+    }
+}
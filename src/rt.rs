@@ -0,0 +1,40 @@
+//! Timers of the async runtime the crate is built for.
+//!
+//! The `async-io` timers are used whenever that feature is enabled, since
+//! they run on async-std, smol and tokio alike. Otherwise tokio provides them.
+use std::{future::Future, time::Duration};
+
+/// A point in time on the clock the timers run on.
+///
+/// Under tokio this follows the runtime clock, so tests can pause it.
+#[cfg(feature = "async-io")]
+pub(crate) use std::time::Instant;
+#[cfg(not(feature = "async-io"))]
+pub(crate) use tokio::time::Instant;
+
+/// Wait until the duration has elapsed.
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "async-io")]
+    async_io::Timer::after(duration).await;
+    #[cfg(not(feature = "async-io"))]
+    tokio::time::sleep(duration).await;
+}
+
+/// Run the future for at most the duration, giving `None` if it did not finish.
+pub(crate) async fn timeout<F>(duration: Duration, future: F) -> Option<F::Output>
+where
+    F: Future,
+{
+    #[cfg(feature = "async-io")]
+    {
+        use futures_util::future::{select, Either};
+
+        let future = std::pin::pin!(future);
+        match select(future, async_io::Timer::after(duration)).await {
+            Either::Left((output, _)) => Some(output),
+            Either::Right(_) => None,
+        }
+    }
+    #[cfg(not(feature = "async-io"))]
+    tokio::time::timeout(duration, future).await.ok()
+}
@@ -0,0 +1,1103 @@
+//! In-process simulator of a Dobot Magician controller.
+//!
+//! The [Simulator] speaks the binary protocol over any [Transport], so the
+//! whole [Dobot](crate::Dobot) API can be exercised without a physical arm.
+//! Queued moves take as long as the configured PTP velocity and acceleration
+//! imply, and the pose is interpolated along the path while they execute.
+//! Use a [VirtualClock] to control the passage of time deterministically,
+//! and a [FaultPlan] to make the controller misbehave on purpose.
+//!
+//! ```no_run
+//! # #[cfg(feature = "tokio")]
+//! # async fn example() -> dobot_fx24::error::Result<()> {
+//! use dobot_fx24::{sim::Simulator, Dobot};
+//!
+//! let sim = Simulator::new();
+//! let mut dobot = Dobot::from_transport(sim.connect()).await?;
+//! dobot.move_to(200.0, 50.0, 0.0, 0.0).await?.wait().await?;
+//! assert_eq!(sim.pose().y, 50.0);
+//! # Ok(())
+//! # }
+//! ```
+use crate::{
+    base::{CommandID, Pose},
+    error::{Error as DobotError, Result as DobotResult},
+    message::{DobotMessage, MessageDecoder, ParamsReader},
+    rt,
+    transport::Transport,
+};
+use futures_util::io::{AsyncReadExt, AsyncWriteExt};
+use std::{
+    collections::{HashMap, VecDeque},
+    io::ErrorKind,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+#[cfg(feature = "tokio")]
+use tokio::io::DuplexStream;
+#[cfg(feature = "tokio")]
+use tokio_util::compat::Compat;
+
+mod fault;
+
+pub use self::fault::{Fault, FaultPlan, Trigger};
+
+/// Length of the rear arm in millimeters.
+pub const REAR_ARM_LENGTH: f32 = 135.0;
+/// Length of the forearm in millimeters.
+pub const FOREARM_LENGTH: f32 = 147.0;
+/// Number of commands the firmware queue can hold.
+///
+/// Queued commands arriving while it is full are acknowledged without an index.
+pub const QUEUE_CAPACITY: usize = 32;
+/// Number of extended IO addresses.
+pub const IO_COUNT: usize = 20;
+/// Number of alarm codes tracked by the controller.
+pub const ALARM_COUNT: usize = 128;
+
+/// Alarm raised when a target has no inverse kinematics solution.
+pub const ALARM_PLAN_INV_CALC: u8 = 0x11;
+/// Alarm raised when a target violates the joint limits.
+pub const ALARM_PLAN_INV_LIMIT: u8 = 0x12;
+
+const JOINT_LIMITS: [(f32, f32); 4] = [
+    (-135.0, 135.0),
+    (-10.0, 90.0),
+    (-20.0, 95.0),
+    (-150.0, 150.0),
+];
+const HOME_POSE: [f32; 4] = [200.0, 0.0, 0.0, 0.0];
+const NO_ALARMS: [u8; ALARM_COUNT / 8] = [0; ALARM_COUNT / 8];
+#[cfg(feature = "tokio")]
+const PIPE_CAPACITY: usize = 4096;
+
+/// State of the simulated end effectors.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EndEffectorState {
+    /// Offset of the tool center point from the forearm tip.
+    pub bias: [f32; 3],
+    /// Whether the suction cup is enabled and sucking.
+    pub suction_cup: (bool, bool),
+    /// Whether the gripper is enabled and gripping.
+    pub gripper: (bool, bool),
+    /// Whether the laser is enabled and turned on.
+    pub laser: (bool, bool),
+}
+
+/// State of one extended IO address.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IoState {
+    /// Multiplexing function, e.g. 1 for digital output.
+    pub multiplexing: u8,
+    /// Digital output level.
+    pub output: bool,
+    /// Digital input level.
+    pub input: bool,
+    /// PWM frequency and duty cycle.
+    pub pwm: (f32, f32),
+    /// Analog input value.
+    pub adc: u16,
+}
+
+/// A clock that only moves when told to.
+///
+/// Clones share the same time, so a test can keep one to advance the clock
+/// of a running [Simulator].
+#[derive(Debug, Clone, Default)]
+pub struct VirtualClock {
+    state: Arc<Mutex<VirtualTime>>,
+}
+
+#[derive(Debug, Default)]
+struct VirtualTime {
+    elapsed: Duration,
+    step: Duration,
+}
+
+impl VirtualClock {
+    /// Create a clock starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a clock that advances by `step` whenever the simulator receives a request.
+    ///
+    /// This keeps polling loops deterministic: every poll observes a fixed amount of progress.
+    pub fn with_step(step: Duration) -> Self {
+        let clock = Self::default();
+        clock.lock().step = step;
+        clock
+    }
+
+    /// Move the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        self.lock().elapsed += duration;
+    }
+
+    /// Get the time elapsed since the clock was created.
+    pub fn elapsed(&self) -> Duration {
+        self.lock().elapsed
+    }
+
+    fn tick(&self) {
+        let mut state = self.lock();
+        let step = state.step;
+        state.elapsed += step;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VirtualTime> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Clock {
+    Real(Instant),
+    Virtual(VirtualClock),
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::Real(Instant::now())
+    }
+}
+
+impl Clock {
+    fn now(&self) -> Duration {
+        match self {
+            Clock::Real(started) => started.elapsed(),
+            Clock::Virtual(clock) => clock.elapsed(),
+        }
+    }
+}
+
+/// A virtual Dobot Magician that answers protocol requests.
+///
+/// The simulator is a cheap handle to shared state: clones observe and
+/// control the same virtual controller.
+#[derive(Clone, Default)]
+pub struct Simulator {
+    controller: Arc<Mutex<Controller>>,
+    clock: Clock,
+}
+
+impl Simulator {
+    /// Create a simulator with the arm at its home pose, running in real time.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a simulator driven by a virtual clock.
+    pub fn with_clock(clock: VirtualClock) -> Self {
+        Self {
+            controller: Default::default(),
+            clock: Clock::Virtual(clock),
+        }
+    }
+
+    /// Spawn a task serving the simulator and get the client end of the connection.
+    ///
+    /// Must be called within a tokio runtime. Other runtimes can spawn
+    /// [Simulator::serve] on one end of their own in-memory stream instead.
+    #[cfg(feature = "tokio")]
+    pub fn connect(&self) -> Compat<DuplexStream> {
+        let (client, server) = tokio::io::duplex(PIPE_CAPACITY);
+        let sim = self.clone();
+        tokio::spawn(async move { sim.serve(crate::transport::from_tokio(server)).await });
+        crate::transport::from_tokio(client)
+    }
+
+    /// Answer requests arriving on the transport until it is closed or a
+    /// [Fault::Disconnect] is injected.
+    pub async fn serve<T>(&self, mut transport: T) -> DobotResult<()>
+    where
+        T: Transport,
+    {
+        let mut decoder = MessageDecoder::new();
+        loop {
+            let request_msg = match decoder.decode() {
+                Some(Ok(msg)) => msg,
+                // the firmware silently drops frames it cannot understand
+                Some(Err(_)) => continue,
+                None => {
+                    let mut buffer = [0u8; 64];
+                    match transport.read(&mut buffer).await {
+                        Ok(0) => break Ok(()),
+                        Ok(len) => decoder.extend(&buffer[..len]),
+                        Err(err) if err.kind() == ErrorKind::BrokenPipe => break Ok(()),
+                        Err(err) => break Err(err.into()),
+                    }
+                    continue;
+                }
+            };
+
+            let faults = self.lock().take_faults(request_msg.id());
+            let mut response = self.handle(&request_msg).map(|msg| msg.to_bytes());
+            for fault in faults.iter() {
+                match (fault, response.as_mut()) {
+                    (Fault::Delay(duration), _) => rt::sleep(*duration).await,
+                    (Fault::Stall { offset, delay }, Some(bytes)) => {
+                        let offset = (*offset).min(bytes.len());
+                        transport.write_all(&bytes[..offset]).await?;
+                        rt::sleep(*delay).await;
+                        bytes.drain(..offset);
+                    }
+                    (Fault::DropResponse, _) => response = None,
+                    (Fault::Disconnect, _) => return Ok(()),
+                    (_, Some(bytes)) => fault::corrupt(bytes, fault),
+                    (_, None) => {}
+                }
+            }
+
+            if let Some(bytes) = response {
+                transport.write_all(bytes.as_slice()).await?;
+            }
+        }
+    }
+
+    /// Process a request and get the response, if the firmware would send one.
+    pub fn handle(&self, request_msg: &DobotMessage) -> Option<DobotMessage> {
+        if let Clock::Virtual(clock) = &self.clock {
+            clock.tick();
+        }
+        let mut controller = self.lock();
+        let params = controller.handle(request_msg).ok()?;
+        controller.update(self.clock.now());
+        DobotMessage::new(
+            request_msg.id(),
+            request_msg.rw(),
+            request_msg.is_queued(),
+            params,
+        )
+        .ok()
+    }
+
+    /// Get the current pose of the virtual arm.
+    pub fn pose(&self) -> Pose {
+        self.lock().pose.clone()
+    }
+
+    /// Get the index of the last executed queued command.
+    pub fn queued_cmd_current_index(&self) -> u64 {
+        self.lock().current_index
+    }
+
+    /// Get the number of queued commands waiting for execution.
+    pub fn queued_cmd_len(&self) -> usize {
+        self.lock().queue.len()
+    }
+
+    /// Check whether a queued command is executing.
+    pub fn is_moving(&self) -> bool {
+        self.lock().active.is_some()
+    }
+
+    /// Get the end effector state.
+    pub fn end_effector(&self) -> EndEffectorState {
+        self.lock().end_effector.clone()
+    }
+
+    /// Get the state of an extended IO address, counting from 1.
+    pub fn io(&self, address: u8) -> Option<IoState> {
+        self.lock().io(address).ok().cloned()
+    }
+
+    /// Set the digital input level seen on an extended IO address.
+    pub fn set_io_input(&self, address: u8, level: bool) {
+        if let Ok(io) = self.lock().io_mut(address) {
+            io.input = level;
+        }
+    }
+
+    /// Set the analog value seen on an extended IO address.
+    pub fn set_io_adc(&self, address: u8, value: u16) {
+        if let Ok(io) = self.lock().io_mut(address) {
+            io.adc = value;
+        }
+    }
+
+    /// Get the raw alarm bits.
+    pub fn alarms(&self) -> [u8; 16] {
+        self.lock().alarms
+    }
+
+    /// Raise an alarm, which halts queued command execution until cleared.
+    ///
+    /// Fails with [`DobotError::InvalidParameter`] if the code is not below [`ALARM_COUNT`].
+    pub fn raise_alarm(&self, code: u8) -> DobotResult<()> {
+        self.lock().raise_alarm(code)
+    }
+
+    /// Replace the faults injected into subsequent requests.
+    pub fn set_fault_plan(&self, plan: FaultPlan) {
+        let mut controller = self.lock();
+        controller.faults = plan;
+    }
+
+    /// Remove all pending faults and undo the lasting ones.
+    pub fn clear_faults(&self) {
+        let mut controller = self.lock();
+        controller.faults = FaultPlan::new();
+        controller.stuck_index = None;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Controller> {
+        let mut controller = self
+            .controller
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        controller.update(self.clock.now());
+        controller
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Path {
+    Joint,
+    Linear,
+    Dwell,
+}
+
+/// Trapezoidal velocity profile of the slowest axis of a move.
+#[derive(Debug, Clone, Copy)]
+struct Profile {
+    distance: f32,
+    velocity: f32,
+    acceleration: f32,
+    duration: Duration,
+}
+
+impl Profile {
+    fn new(distance: f32, velocity: f32, acceleration: f32) -> Self {
+        // products of large parameters and ratios can overflow to infinity
+        let velocity = velocity.clamp(1e-3, f32::MAX);
+        let acceleration = acceleration.clamp(1e-3, f32::MAX);
+        let seconds = if distance <= velocity * velocity / acceleration {
+            // the axis never reaches full speed
+            2.0 * (distance / acceleration).sqrt()
+        } else {
+            distance / velocity + velocity / acceleration
+        };
+        Self {
+            distance,
+            velocity,
+            acceleration,
+            duration: Duration::try_from_secs_f32(seconds.max(0.0)).unwrap_or(Duration::MAX),
+        }
+    }
+
+    fn dwell(duration: Duration) -> Self {
+        Self {
+            distance: 0.0,
+            velocity: 0.0,
+            acceleration: 0.0,
+            duration,
+        }
+    }
+
+    /// Get the fraction of the distance covered after the elapsed time.
+    fn fraction(&self, elapsed: Duration) -> f32 {
+        if self.distance <= 0.0 || elapsed >= self.duration {
+            return 1.0;
+        }
+        let Self {
+            distance,
+            acceleration,
+            ..
+        } = *self;
+        let total = self.duration.as_secs_f32();
+        let t = elapsed.as_secs_f32();
+        let t_acc = (self.velocity / acceleration).min(total / 2.0);
+        let position = if t < t_acc {
+            0.5 * acceleration * t * t
+        } else if t < total - t_acc {
+            0.5 * acceleration * t_acc * t_acc + acceleration * t_acc * (t - t_acc)
+        } else {
+            let remaining = total - t;
+            distance - 0.5 * acceleration * remaining * remaining
+        };
+        (position / distance).clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    from: Pose,
+    target: Pose,
+    path: Path,
+    profile: Profile,
+}
+
+#[derive(Debug)]
+struct ActiveMotion {
+    index: u64,
+    start: Duration,
+    segments: VecDeque<Segment>,
+}
+
+struct Controller {
+    now: Duration,
+    device_sn: Vec<u8>,
+    device_name: Vec<u8>,
+    device_version: [u8; 3],
+    with_l: bool,
+    pose: Pose,
+    pose_l: f32,
+    home_params: [f32; 4],
+    end_effector: EndEffectorState,
+    ios: Vec<IoState>,
+    alarms: [u8; 16],
+    ptp_joint_params: [f32; 8],
+    ptp_coordinate_params: [f32; 4],
+    ptp_jump_params: [f32; 2],
+    ptp_common_params: [f32; 2],
+    queue: VecDeque<(u64, DobotMessage)>,
+    queue_running: bool,
+    active: Option<ActiveMotion>,
+    cursor: Duration,
+    last_index: u64,
+    current_index: u64,
+    stored_params: HashMap<CommandID, Vec<u8>>,
+    faults: FaultPlan,
+    stuck_index: Option<u64>,
+}
+
+impl Default for Controller {
+    fn default() -> Self {
+        let mut controller = Self {
+            now: Duration::ZERO,
+            device_sn: b"DOBOT-SIM-0001".to_vec(),
+            device_name: b"Dobot Magician".to_vec(),
+            device_version: [3, 8, 0],
+            with_l: false,
+            pose: Pose {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+                r: 0.0,
+                j1: 0.0,
+                j2: 0.0,
+                j3: 0.0,
+                j4: 0.0,
+            },
+            pose_l: 0.0,
+            home_params: HOME_POSE,
+            end_effector: EndEffectorState {
+                bias: [59.7, 0.0, 0.0],
+                ..Default::default()
+            },
+            ios: vec![IoState::default(); IO_COUNT],
+            alarms: NO_ALARMS,
+            ptp_joint_params: [200.0; 8],
+            ptp_coordinate_params: [200.0; 4],
+            ptp_jump_params: [10.0, 200.0],
+            ptp_common_params: [100.0, 100.0],
+            queue: VecDeque::new(),
+            queue_running: false,
+            active: None,
+            cursor: Duration::ZERO,
+            last_index: 0,
+            current_index: 0,
+            stored_params: HashMap::new(),
+            faults: FaultPlan::new(),
+            stuck_index: None,
+        };
+        let [x, y, z, r] = HOME_POSE;
+        controller.pose = controller
+            .inverse_kinematics(x, y, z, r)
+            .expect("home pose is reachable");
+        controller
+    }
+}
+
+impl Controller {
+    fn handle(&mut self, request_msg: &DobotMessage) -> DobotResult<Vec<u8>> {
+        if request_msg.is_queued() {
+            if !request_msg.rw() {
+                return Err(DobotError::InvalidParameter(
+                    "cannot queue the command".into(),
+                ));
+            }
+            if self.queue.len() >= QUEUE_CAPACITY {
+                // a full queue is acknowledged without an index
+                return Ok(vec![]);
+            }
+            self.last_index += 1;
+            self.queue.push_back((self.last_index, request_msg.clone()));
+            return Ok(self.last_index.to_le_bytes().to_vec());
+        }
+
+        if !request_msg.rw() {
+            return self.get(request_msg);
+        }
+
+        // motions requested outside of the queue complete immediately
+        match self.plan(request_msg)? {
+            Some(segments) => {
+                if let Some(segment) = segments.last() {
+                    self.pose = segment.target.clone();
+                }
+            }
+            None => self.set(request_msg)?,
+        }
+        Ok(vec![])
+    }
+
+    /// Take the faults triggered by a request and apply those affecting the controller.
+    fn take_faults(&mut self, id: CommandID) -> Vec<Fault> {
+        let faults = self.faults.take(id);
+        for fault in faults.iter() {
+            match *fault {
+                Fault::Alarm(code) => {
+                    // the plan only accepts codes within the alarm bitmap
+                    let _ = self.raise_alarm(code);
+                }
+                Fault::StuckQueueIndex => {
+                    self.stuck_index.get_or_insert(self.current_index);
+                }
+                _ => {}
+            }
+        }
+        faults
+    }
+
+    /// Advance queued command execution up to the given time.
+    fn update(&mut self, now: Duration) {
+        self.now = self.now.max(now);
+        if self.alarms != NO_ALARMS {
+            // alarms stop the arm where it is
+            self.active = None;
+        }
+
+        loop {
+            if let Some(mut motion) = self.active.take() {
+                match motion.segments.pop_front() {
+                    Some(segment) => {
+                        let elapsed = self.now.saturating_sub(motion.start);
+                        if elapsed < segment.profile.duration {
+                            self.pose = self.interpolate(&segment, elapsed);
+                            motion.segments.push_front(segment);
+                            self.active = Some(motion);
+                            return;
+                        }
+                        motion.start += segment.profile.duration;
+                        self.pose = segment.target;
+                        self.active = Some(motion);
+                    }
+                    None => {
+                        self.current_index = motion.index;
+                        self.cursor = motion.start;
+                    }
+                }
+                continue;
+            }
+
+            if !self.queue_running || self.alarms != NO_ALARMS {
+                break;
+            }
+            let (index, msg) = match self.queue.pop_front() {
+                Some(entry) => entry,
+                None => break,
+            };
+            match self.plan(&msg) {
+                Ok(Some(segments)) => {
+                    self.active = Some(ActiveMotion {
+                        index,
+                        start: self.cursor,
+                        segments: segments.into(),
+                    });
+                }
+                Ok(None) => {
+                    if self.set(&msg).is_ok() {
+                        self.current_index = index;
+                    }
+                }
+                // failed motions raise an alarm and never complete
+                Err(_) => {}
+            }
+        }
+
+        // the next command starts as soon as it arrives
+        self.cursor = self.now;
+    }
+
+    /// Plan the path of a motion command, or get `None` for other commands.
+    fn plan(&mut self, request_msg: &DobotMessage) -> DobotResult<Option<Vec<Segment>>> {
+        let mut reader = ParamsReader::new(request_msg.params());
+        let from = self.pose.clone();
+        let segments = match request_msg.id() {
+            CommandID::SetHomeCmd => {
+                let [x, y, z, r] = self.home_params;
+                let target = self.reach(x, y, z, r)?;
+                vec![self.joint_segment(&from, target)]
+            }
+            CommandID::SetPtpCmd => {
+                let mode = reader.u8()?;
+                let [a, b, c, d] = reader.f32s()?;
+                self.plan_ptp(mode, a, b, c, d)?
+            }
+            CommandID::SetPtpWithLCmd => {
+                let mode = reader.u8()?;
+                let [a, b, c, d, l] = reader.f32s()?;
+                self.pose_l = l;
+                self.plan_ptp(mode, a, b, c, d)?
+            }
+            CommandID::SetCpCmd | CommandID::SetCpLeCmd => {
+                let mode = reader.u8()?;
+                let [x, y, z] = reader.f32s()?;
+                let target = if mode == 0 {
+                    self.reach(from.x + x, from.y + y, from.z + z, from.r)?
+                } else {
+                    self.reach(x, y, z, from.r)?
+                };
+                vec![self.linear_segment(&from, target)]
+            }
+            CommandID::SetWaitCmd => {
+                let millis = reader.u32()?;
+                vec![Segment {
+                    from: from.clone(),
+                    target: from,
+                    path: Path::Dwell,
+                    profile: Profile::dwell(Duration::from_millis(millis as u64)),
+                }]
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(segments))
+    }
+
+    fn get(&mut self, request_msg: &DobotMessage) -> DobotResult<Vec<u8>> {
+        let mut reader = ParamsReader::new(request_msg.params());
+        let params = match request_msg.id() {
+            CommandID::GetSetDeviceSn => self.device_sn.clone(),
+            CommandID::GetSetDeviceName => self.device_name.clone(),
+            CommandID::GetDeviceVersion => self.device_version.to_vec(),
+            CommandID::GetDeviceWithL => vec![self.with_l as u8],
+            CommandID::GetDeviceTime => (self.now.as_millis() as u32).to_le_bytes().to_vec(),
+            CommandID::GetPose => {
+                let Pose {
+                    x,
+                    y,
+                    z,
+                    r,
+                    j1,
+                    j2,
+                    j3,
+                    j4,
+                } = self.pose;
+                f32s(&[x, y, z, r, j1, j2, j3, j4])
+            }
+            CommandID::GetPoseL => f32s(&[self.pose_l]),
+            CommandID::GetAlarmsState => self.alarms.to_vec(),
+            CommandID::GetSetHomeParams => f32s(&self.home_params),
+            CommandID::GetSetEndEffectorParams => f32s(&self.end_effector.bias),
+            CommandID::GetSetEndEffectorLaser => {
+                let (enabled, on) = self.end_effector.laser;
+                vec![enabled as u8, on as u8]
+            }
+            CommandID::GetSetEndEffectorSuctionCup => {
+                let (enabled, on) = self.end_effector.suction_cup;
+                vec![enabled as u8, on as u8]
+            }
+            CommandID::GetSetEndEffectorGripper => {
+                let (enabled, on) = self.end_effector.gripper;
+                vec![enabled as u8, on as u8]
+            }
+            CommandID::GetSetIoMultiplexing => {
+                let address = reader.u8()?;
+                vec![address, self.io(address)?.multiplexing]
+            }
+            CommandID::GetSetIoDo => {
+                let address = reader.u8()?;
+                vec![address, self.io(address)?.output as u8]
+            }
+            CommandID::GetSetIoPwm => {
+                let address = reader.u8()?;
+                let (frequency, duty_cycle) = self.io(address)?.pwm;
+                let mut params = vec![address];
+                params.extend(f32s(&[frequency, duty_cycle]));
+                params
+            }
+            CommandID::GetIoDi => {
+                let address = reader.u8()?;
+                vec![address, self.io(address)?.input as u8]
+            }
+            CommandID::GetIoAdc => {
+                let address = reader.u8()?;
+                let mut params = vec![address];
+                params.extend(self.io(address)?.adc.to_le_bytes().iter());
+                params
+            }
+            CommandID::GetSetPtpJointParams => f32s(&self.ptp_joint_params),
+            CommandID::GetSetPtpCoordinateParams => f32s(&self.ptp_coordinate_params),
+            CommandID::GetSetPtpJumpParams => f32s(&self.ptp_jump_params),
+            CommandID::GetSetPtpCommonParams => f32s(&self.ptp_common_params),
+            CommandID::GetQueuedCmdLeftSpace => ((QUEUE_CAPACITY - self.queue.len()) as u32)
+                .to_le_bytes()
+                .to_vec(),
+            CommandID::SetQueuedCmdCurrentIndex => self
+                .stuck_index
+                .unwrap_or(self.current_index)
+                .to_le_bytes()
+                .to_vec(),
+            id => self.stored_params.get(&id).cloned().unwrap_or_default(),
+        };
+        Ok(params)
+    }
+
+    fn set(&mut self, request_msg: &DobotMessage) -> DobotResult<()> {
+        let mut reader = ParamsReader::new(request_msg.params());
+        match request_msg.id() {
+            CommandID::GetSetDeviceSn => self.device_sn = request_msg.params().clone(),
+            CommandID::GetSetDeviceName => self.device_name = request_msg.params().clone(),
+            CommandID::GetDeviceWithL => self.with_l = reader.u8()? != 0,
+            CommandID::ResetPose => {
+                let manual = reader.u8()? != 0;
+                let (j2, j3) = (reader.f32()?, reader.f32()?);
+                if manual {
+                    let Pose { j1, j4, .. } = self.pose;
+                    self.pose = self.forward_kinematics(j1, j2, j3, j4);
+                }
+            }
+            CommandID::ClearAllAlarmsState => self.alarms = NO_ALARMS,
+            CommandID::GetSetHomeParams => self.home_params = reader.f32s()?,
+            CommandID::GetSetEndEffectorParams => self.end_effector.bias = reader.f32s()?,
+            CommandID::GetSetEndEffectorLaser => {
+                self.end_effector.laser = (reader.u8()? != 0, reader.u8()? != 0)
+            }
+            CommandID::GetSetEndEffectorSuctionCup => {
+                self.end_effector.suction_cup = (reader.u8()? != 0, reader.u8()? != 0)
+            }
+            CommandID::GetSetEndEffectorGripper => {
+                self.end_effector.gripper = (reader.u8()? != 0, reader.u8()? != 0)
+            }
+            CommandID::GetSetPtpJointParams => self.ptp_joint_params = rates(&mut reader)?,
+            CommandID::GetSetPtpCoordinateParams => {
+                self.ptp_coordinate_params = rates(&mut reader)?
+            }
+            CommandID::GetSetPtpJumpParams => self.ptp_jump_params = reader.f32s()?,
+            CommandID::GetSetPtpCommonParams => self.ptp_common_params = rates(&mut reader)?,
+            CommandID::GetSetIoMultiplexing => {
+                let address = reader.u8()?;
+                self.io_mut(address)?.multiplexing = reader.u8()?;
+            }
+            CommandID::GetSetIoDo => {
+                let address = reader.u8()?;
+                self.io_mut(address)?.output = reader.u8()? != 0;
+            }
+            CommandID::GetSetIoPwm => {
+                let address = reader.u8()?;
+                self.io_mut(address)?.pwm = (reader.f32()?, reader.f32()?);
+            }
+            CommandID::SetQueuedCmdStartExec => self.queue_running = true,
+            CommandID::SetQueuedCmdStopExec => self.queue_running = false,
+            CommandID::SetQueuedCmdForceStopExec => {
+                self.queue_running = false;
+                self.active = None;
+            }
+            CommandID::SetQueuedCmdClear => {
+                self.queue.clear();
+                self.active = None;
+                self.last_index = 0;
+                self.current_index = 0;
+            }
+            id => {
+                self.stored_params.insert(id, request_msg.params().clone());
+            }
+        }
+        Ok(())
+    }
+
+    fn io(&self, address: u8) -> DobotResult<&IoState> {
+        (address as usize)
+            .checked_sub(1)
+            .and_then(|index| self.ios.get(index))
+            .ok_or_else(|| DobotError::InvalidParameter(format!("no IO address {}", address)))
+    }
+
+    fn io_mut(&mut self, address: u8) -> DobotResult<&mut IoState> {
+        (address as usize)
+            .checked_sub(1)
+            .and_then(move |index| self.ios.get_mut(index))
+            .ok_or_else(|| DobotError::InvalidParameter(format!("no IO address {}", address)))
+    }
+
+    fn raise_alarm(&mut self, code: u8) -> DobotResult<()> {
+        let byte = self
+            .alarms
+            .get_mut(code as usize / 8)
+            .ok_or_else(|| DobotError::InvalidParameter(format!("no alarm code {}", code)))?;
+        *byte |= 1 << (code % 8);
+        Ok(())
+    }
+
+    fn plan_ptp(&mut self, mode: u8, a: f32, b: f32, c: f32, d: f32) -> DobotResult<Vec<Segment>> {
+        let from = self.pose.clone();
+        let segments = match mode {
+            0x00 | 0x09 => {
+                let target = self.reach(a, b, c, d)?;
+                self.jump_segments(&from, target)?
+            }
+            0x01 => {
+                let target = self.reach(a, b, c, d)?;
+                vec![self.joint_segment(&from, target)]
+            }
+            0x02 => {
+                let target = self.reach(a, b, c, d)?;
+                vec![self.linear_segment(&from, target)]
+            }
+            0x03 => {
+                let target = self.reach_joints(a, b, c, d)?;
+                self.jump_segments(&from, target)?
+            }
+            0x04 => {
+                let target = self.reach_joints(a, b, c, d)?;
+                vec![self.joint_segment(&from, target)]
+            }
+            0x05 => {
+                let target = self.reach_joints(a, b, c, d)?;
+                vec![self.linear_segment(&from, target)]
+            }
+            0x06 => {
+                let target =
+                    self.reach_joints(from.j1 + a, from.j2 + b, from.j3 + c, from.j4 + d)?;
+                vec![self.joint_segment(&from, target)]
+            }
+            0x07 => {
+                let target = self.reach(from.x + a, from.y + b, from.z + c, from.r + d)?;
+                vec![self.linear_segment(&from, target)]
+            }
+            0x08 => {
+                let target = self.reach(from.x + a, from.y + b, from.z + c, from.r + d)?;
+                vec![self.joint_segment(&from, target)]
+            }
+            _ => {
+                return Err(DobotError::InvalidParameter(format!(
+                    "unknown PTP mode {}",
+                    mode
+                )))
+            }
+        };
+        Ok(segments)
+    }
+
+    /// Lift to the jump height, travel above the target, then lower onto it.
+    fn jump_segments(&mut self, from: &Pose, target: Pose) -> DobotResult<Vec<Segment>> {
+        let [height, limit] = self.ptp_jump_params;
+        let floor = from.z.max(target.z);
+        let top = (floor + height).min(limit).max(floor);
+        let lifted = self.reach(from.x, from.y, top, from.r)?;
+        let above = self.reach(target.x, target.y, top, target.r)?;
+
+        Ok(vec![
+            self.linear_segment(from, lifted.clone()),
+            self.joint_segment(&lifted, above.clone()),
+            self.linear_segment(&above, target),
+        ])
+    }
+
+    fn joint_segment(&self, from: &Pose, target: Pose) -> Segment {
+        let [velocity_ratio, acceleration_ratio] = self.velocity_ratios();
+        let deltas = [
+            target.j1 - from.j1,
+            target.j2 - from.j2,
+            target.j3 - from.j3,
+            target.j4 - from.j4,
+        ];
+        let profile = deltas
+            .iter()
+            .enumerate()
+            .map(|(axis, delta)| {
+                Profile::new(
+                    delta.abs(),
+                    self.ptp_joint_params[axis] * velocity_ratio,
+                    self.ptp_joint_params[axis + 4] * acceleration_ratio,
+                )
+            })
+            .max_by_key(|profile| profile.duration)
+            .unwrap();
+
+        Segment {
+            from: from.clone(),
+            target,
+            path: Path::Joint,
+            profile,
+        }
+    }
+
+    fn linear_segment(&self, from: &Pose, target: Pose) -> Segment {
+        let [velocity_ratio, acceleration_ratio] = self.velocity_ratios();
+        let [xyz_velocity, r_velocity, xyz_acceleration, r_acceleration] =
+            self.ptp_coordinate_params;
+        let distance = ((target.x - from.x).powi(2)
+            + (target.y - from.y).powi(2)
+            + (target.z - from.z).powi(2))
+        .sqrt();
+        let translation = Profile::new(
+            distance,
+            xyz_velocity * velocity_ratio,
+            xyz_acceleration * acceleration_ratio,
+        );
+        let rotation = Profile::new(
+            (target.r - from.r).abs(),
+            r_velocity * velocity_ratio,
+            r_acceleration * acceleration_ratio,
+        );
+        let profile = if translation.duration >= rotation.duration {
+            translation
+        } else {
+            rotation
+        };
+
+        Segment {
+            from: from.clone(),
+            target,
+            path: Path::Linear,
+            profile,
+        }
+    }
+
+    fn velocity_ratios(&self) -> [f32; 2] {
+        let [velocity_ratio, acceleration_ratio] = self.ptp_common_params;
+        [velocity_ratio / 100.0, acceleration_ratio / 100.0]
+    }
+
+    fn interpolate(&self, segment: &Segment, elapsed: Duration) -> Pose {
+        let fraction = segment.profile.fraction(elapsed);
+        let (from, target) = (&segment.from, &segment.target);
+        let lerp = |start: f32, end: f32| start + (end - start) * fraction;
+        let joint_pose = || {
+            self.forward_kinematics(
+                lerp(from.j1, target.j1),
+                lerp(from.j2, target.j2),
+                lerp(from.j3, target.j3),
+                lerp(from.j4, target.j4),
+            )
+        };
+
+        match segment.path {
+            Path::Joint => joint_pose(),
+            Path::Linear => self
+                .inverse_kinematics(
+                    lerp(from.x, target.x),
+                    lerp(from.y, target.y),
+                    lerp(from.z, target.z),
+                    lerp(from.r, target.r),
+                )
+                .unwrap_or_else(joint_pose),
+            Path::Dwell => target.clone(),
+        }
+    }
+
+    /// Get the pose at the given coordinates, raising an alarm if it is out of reach.
+    fn reach(&mut self, x: f32, y: f32, z: f32, r: f32) -> DobotResult<Pose> {
+        match self.inverse_kinematics(x, y, z, r) {
+            Some(pose) => {
+                self.check_joint_limits(&pose)?;
+                Ok(pose)
+            }
+            None => {
+                self.raise_alarm(ALARM_PLAN_INV_CALC)?;
+                Err(DobotError::InvalidParameter(format!(
+                    "({}, {}, {}) is out of reach",
+                    x, y, z
+                )))
+            }
+        }
+    }
+
+    /// Get the pose at the given joint angles, raising an alarm if they exceed the limits.
+    fn reach_joints(&mut self, j1: f32, j2: f32, j3: f32, j4: f32) -> DobotResult<Pose> {
+        let pose = self.forward_kinematics(j1, j2, j3, j4);
+        self.check_joint_limits(&pose)?;
+        Ok(pose)
+    }
+
+    fn check_joint_limits(&mut self, pose: &Pose) -> DobotResult<()> {
+        let within_limits = [pose.j1, pose.j2, pose.j3, pose.j4]
+            .iter()
+            .zip(JOINT_LIMITS.iter())
+            .all(|(angle, (min, max))| min <= angle && angle <= max);
+        if !within_limits {
+            self.raise_alarm(ALARM_PLAN_INV_LIMIT)?;
+            return Err(DobotError::InvalidParameter(format!(
+                "joint angles ({}, {}, {}, {}) exceed the limits",
+                pose.j1, pose.j2, pose.j3, pose.j4
+            )));
+        }
+        Ok(())
+    }
+
+    /// Compute the pose from joint angles in degrees.
+    fn forward_kinematics(&self, j1: f32, j2: f32, j3: f32, j4: f32) -> Pose {
+        let [bias_x, _, bias_z] = self.end_effector.bias;
+        let radius = REAR_ARM_LENGTH * j2.to_radians().sin()
+            + FOREARM_LENGTH * j3.to_radians().cos()
+            + bias_x;
+        let z = REAR_ARM_LENGTH * j2.to_radians().cos() - FOREARM_LENGTH * j3.to_radians().sin()
+            + bias_z;
+
+        Pose {
+            x: radius * j1.to_radians().cos(),
+            y: radius * j1.to_radians().sin(),
+            z,
+            r: j1 + j4,
+            j1,
+            j2,
+            j3,
+            j4,
+        }
+    }
+
+    /// Compute joint angles reaching the given coordinates, if any.
+    fn inverse_kinematics(&self, x: f32, y: f32, z: f32, r: f32) -> Option<Pose> {
+        let [bias_x, _, bias_z] = self.end_effector.bias;
+        let j1 = y.atan2(x).to_degrees();
+        let radius = (x * x + y * y).sqrt() - bias_x;
+        let height = z - bias_z;
+        let distance = (radius * radius + height * height).sqrt();
+
+        // angle between the wrist direction and the rear arm, by the law of cosines
+        let cos_gamma = (REAR_ARM_LENGTH.powi(2) + distance.powi(2) - FOREARM_LENGTH.powi(2))
+            / (2.0 * REAR_ARM_LENGTH * distance);
+        if !(-1.0..=1.0).contains(&cos_gamma) {
+            return None;
+        }
+        let j2 = radius.atan2(height) - cos_gamma.acos();
+        let elbow_radius = REAR_ARM_LENGTH * j2.sin();
+        let elbow_height = REAR_ARM_LENGTH * j2.cos();
+        let j3 = (elbow_height - height).atan2(radius - elbow_radius);
+
+        let (j2, j3) = (j2.to_degrees(), j3.to_degrees());
+        let mut pose = self.forward_kinematics(j1, j2, j3, r - j1);
+        // report the requested coordinates rather than the recomputed ones
+        pose.x = x;
+        pose.y = y;
+        pose.z = z;
+        Some(pose)
+    }
+}
+
+fn f32s(values: &[f32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// Read velocities, accelerations or ratios, which must be finite and positive.
+fn rates<const N: usize>(reader: &mut ParamsReader) -> DobotResult<[f32; N]> {
+    let values = reader.f32s::<N>()?;
+    match values
+        .iter()
+        .find(|value| !(value.is_finite() && **value > 0.0))
+    {
+        Some(value) => Err(DobotError::InvalidParameter(format!(
+            "{} is not a valid rate",
+            value
+        ))),
+        None => Ok(values),
+    }
+}
@@ -0,0 +1,144 @@
+use crate::message::CommandID;
+use std::time::Duration;
+
+/// A misbehavior the [Simulator](super::Simulator) can inject.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fault {
+    /// Send the response with a wrong checksum.
+    CorruptChecksum,
+    /// Leave out `count` bytes of the response starting at `offset`.
+    DropBytes { offset: usize, count: usize },
+    /// Send `count` bytes of the response starting at `offset` twice.
+    DuplicateBytes { offset: usize, count: usize },
+    /// Handle the request but never send the response.
+    DropResponse,
+    /// Wait before sending the response.
+    Delay(Duration),
+    /// Send the first `offset` bytes of the response, then wait before sending the rest.
+    Stall { offset: usize, delay: Duration },
+    /// Raise the alarm with the given code before handling the request.
+    ///
+    /// The code must be below [`ALARM_COUNT`](super::ALARM_COUNT).
+    Alarm(u8),
+    /// Freeze the reported queued command index at its current value.
+    ///
+    /// The arm keeps executing; only `GetQueuedCmdCurrentIndex` stops advancing
+    /// until the faults are cleared.
+    StuckQueueIndex,
+    /// Close the connection instead of responding.
+    Disconnect,
+}
+
+/// Decides which requests a fault applies to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trigger {
+    /// The n-th request after the plan is installed, counting from 0.
+    Request(u64),
+    /// The next request with the given command ID.
+    NextCommand(CommandID),
+    /// Every request with the given command ID.
+    EveryCommand(CommandID),
+}
+
+/// A list of faults and the requests that trigger them.
+///
+/// ```
+/// use dobot_fx24::{message::CommandID, sim::{Fault, FaultPlan}};
+/// use std::time::Duration;
+///
+/// let plan = FaultPlan::new()
+///     .at_request(2, Fault::CorruptChecksum)
+///     .on_every_command(CommandID::GetPose, Fault::Delay(Duration::from_millis(50)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FaultPlan {
+    entries: Vec<(Trigger, Fault)>,
+    requests: u64,
+}
+
+impl FaultPlan {
+    /// Create an empty plan.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a fault with the given trigger.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the fault is an [`Fault::Alarm`] with a code the controller does not track.
+    pub fn with(mut self, trigger: Trigger, fault: Fault) -> Self {
+        if let Fault::Alarm(code) = fault {
+            assert!(
+                (code as usize) < super::ALARM_COUNT,
+                "alarm code {} is out of range",
+                code
+            );
+        }
+        self.entries.push((trigger, fault));
+        self
+    }
+
+    /// Inject a fault into the n-th request, counting from 0.
+    pub fn at_request(self, index: u64, fault: Fault) -> Self {
+        self.with(Trigger::Request(index), fault)
+    }
+
+    /// Inject a fault into the next request with the given command ID.
+    pub fn on_next_command(self, id: CommandID, fault: Fault) -> Self {
+        self.with(Trigger::NextCommand(id), fault)
+    }
+
+    /// Inject a fault into every request with the given command ID.
+    pub fn on_every_command(self, id: CommandID, fault: Fault) -> Self {
+        self.with(Trigger::EveryCommand(id), fault)
+    }
+
+    /// Check whether no faults are left to inject.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Count the request and take the faults it triggers.
+    pub(super) fn take(&mut self, id: CommandID) -> Vec<Fault> {
+        let index = self.requests;
+        self.requests += 1;
+
+        let mut faults = vec![];
+        self.entries.retain(|(trigger, fault)| {
+            let (triggered, once) = match *trigger {
+                Trigger::Request(n) => (n == index, true),
+                Trigger::NextCommand(trigger_id) => (trigger_id == id, true),
+                Trigger::EveryCommand(trigger_id) => (trigger_id == id, false),
+            };
+            if triggered {
+                faults.push(fault.clone());
+            }
+            !(triggered && once)
+        });
+        faults
+    }
+}
+
+/// Apply the byte-level faults to an encoded response.
+pub(super) fn corrupt(bytes: &mut Vec<u8>, fault: &Fault) {
+    match *fault {
+        Fault::CorruptChecksum => {
+            if let Some(checksum) = bytes.last_mut() {
+                *checksum = checksum.wrapping_add(1);
+            }
+        }
+        Fault::DropBytes { offset, count } => {
+            let start = offset.min(bytes.len());
+            let end = offset.saturating_add(count).min(bytes.len());
+            bytes.drain(start..end);
+        }
+        Fault::DuplicateBytes { offset, count } => {
+            let start = offset.min(bytes.len());
+            let end = offset.saturating_add(count).min(bytes.len());
+            let duplicate = bytes[start..end].to_vec();
+            bytes.splice(end..end, duplicate);
+        }
+        _ => {}
+    }
+}
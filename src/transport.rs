@@ -0,0 +1,155 @@
+//! Byte streams that can carry the Dobot protocol.
+use crate::error::Result as DobotResult;
+use futures_io::{AsyncRead, AsyncWrite};
+use std::time::Duration;
+
+/// A bidirectional byte stream connected to a Dobot controller.
+///
+/// It is implemented for every [futures-io](futures_io) `AsyncRead +
+/// AsyncWrite + Unpin + Send` type, so streams of async-std or smol can be
+/// used with [Dobot::from_transport](crate::Dobot::from_transport) directly.
+/// Tokio streams are adapted with [from_tokio].
+pub trait Transport: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T> Transport for T where T: AsyncRead + AsyncWrite + Unpin + Send {}
+
+/// The baud rate of the controller's serial port.
+pub const BAUD_RATE: u32 = 115200;
+
+/// Adapt a tokio stream, e.g. a serial port or a TCP bridge, into a [Transport].
+#[cfg(feature = "tokio")]
+pub fn from_tokio<T>(stream: T) -> tokio_util::compat::Compat<T>
+where
+    T: tokio::io::AsyncRead + tokio::io::AsyncWrite,
+{
+    tokio_util::compat::TokioAsyncReadCompatExt::compat(stream)
+}
+
+/// Open the serial port at the path with the controller's settings.
+///
+/// The tokio serial port is used if the `tokio` feature is enabled, and
+/// [AsyncSerialPort] otherwise.
+#[cfg(feature = "tokio")]
+pub(crate) fn open_serial(path: &str) -> DobotResult<impl Transport> {
+    use tokio_serial::SerialPortBuilderExt;
+
+    let serial = serial_builder(path, Duration::from_secs(1)).open_native_async()?;
+    Ok(from_tokio(serial))
+}
+
+#[cfg(all(not(feature = "tokio"), feature = "async-io", unix))]
+pub(crate) fn open_serial(path: &str) -> DobotResult<impl Transport> {
+    AsyncSerialPort::open(path)
+}
+
+fn serial_builder(path: &str, timeout: Duration) -> serialport::SerialPortBuilder {
+    use serialport::{DataBits, FlowControl, Parity, StopBits};
+
+    serialport::new(path, BAUD_RATE)
+        .data_bits(DataBits::Eight)
+        .flow_control(FlowControl::None)
+        .parity(Parity::None)
+        .stop_bits(StopBits::One)
+        .timeout(timeout)
+}
+
+#[cfg(all(feature = "async-io", unix))]
+pub use self::async_serial::AsyncSerialPort;
+
+#[cfg(all(feature = "async-io", unix))]
+mod async_serial {
+    use super::serial_builder;
+    use crate::error::Result as DobotResult;
+    use async_io::{Async, IoSafe};
+    use futures_io::{AsyncRead, AsyncWrite};
+    use serialport::TTYPort;
+    use std::{
+        io::{self, ErrorKind, Read, Write},
+        os::unix::io::{AsFd, AsRawFd, BorrowedFd},
+        pin::Pin,
+        task::{Context, Poll},
+        time::Duration,
+    };
+
+    /// A serial port driven by the `async-io` reactor of async-std and smol.
+    pub struct AsyncSerialPort {
+        port: Async<NonBlocking>,
+    }
+
+    impl AsyncSerialPort {
+        /// Open the serial port at the path with the controller's settings.
+        pub fn open(path: &str) -> DobotResult<Self> {
+            let port = serial_builder(path, Duration::ZERO).open_native()?;
+            Ok(Self {
+                port: Async::new(NonBlocking(port))?,
+            })
+        }
+    }
+
+    impl AsyncRead for AsyncSerialPort {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.port).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncWrite for AsyncSerialPort {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.port).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.port).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.port).poll_close(cx)
+        }
+    }
+
+    /// A port with zero timeout, whose timeouts are reported as `WouldBlock`
+    /// so the reactor waits for readiness instead.
+    struct NonBlocking(TTYPort);
+
+    impl NonBlocking {
+        fn map_timeout(error: io::Error) -> io::Error {
+            match error.kind() {
+                ErrorKind::TimedOut => ErrorKind::WouldBlock.into(),
+                _ => error,
+            }
+        }
+    }
+
+    impl Read for NonBlocking {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.0.read(buf).map_err(Self::map_timeout)
+        }
+    }
+
+    impl Write for NonBlocking {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.write(buf).map_err(Self::map_timeout)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.flush().map_err(Self::map_timeout)
+        }
+    }
+
+    impl AsFd for NonBlocking {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            // SAFETY: the descriptor is owned by the port and lives as long as it
+            unsafe { BorrowedFd::borrow_raw(self.0.as_raw_fd()) }
+        }
+    }
+
+    // SAFETY: reading and writing never closes or replaces the descriptor
+    unsafe impl IoSafe for NonBlocking {}
+}
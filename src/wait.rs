@@ -0,0 +1,147 @@
+//! Options for waiting on queued commands.
+use crate::{
+    alarm::AlarmPolicy,
+    error::{Error as DobotError, Result as DobotResult},
+    rt::Instant,
+};
+use std::time::Duration;
+
+/// The default delay between two polls of the executed index.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// The default delay between two checks of the alarm state.
+pub const DEFAULT_ALARM_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Decides how the executed index is polled while waiting for queued commands.
+///
+/// The executed index does not change while a single motion runs, so the
+/// stall timeout must be longer than the slowest command in the queue.
+///
+/// The firmware stops advancing the queue once an alarm is raised, so the
+/// alarm state is checked periodically as well, and whenever a limit is hit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaitOptions {
+    /// Pause between two polls.
+    pub poll_interval: Duration,
+    /// Longest time to wait in total, or `None` to wait forever.
+    pub timeout: Option<Duration>,
+    /// Longest time the executed index may stay unchanged, or `None` to never give up.
+    pub stall_timeout: Option<Duration>,
+    /// Pause between two checks of the alarm state, or `None` to never check it.
+    pub alarm_interval: Option<Duration>,
+    /// How to recover once an alarm is found.
+    pub alarm_policy: AlarmPolicy,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self::new(DEFAULT_POLL_INTERVAL)
+    }
+}
+
+impl WaitOptions {
+    /// Create options polling at the given interval without any limit.
+    pub fn new(poll_interval: Duration) -> Self {
+        Self {
+            poll_interval,
+            timeout: None,
+            stall_timeout: None,
+            alarm_interval: Some(DEFAULT_ALARM_INTERVAL),
+            alarm_policy: AlarmPolicy::default(),
+        }
+    }
+
+    /// Set the longest time to wait in total.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the longest time the executed index may stay unchanged.
+    pub fn with_stall_timeout(mut self, stall_timeout: Duration) -> Self {
+        self.stall_timeout = Some(stall_timeout);
+        self
+    }
+
+    /// Set the pause between two checks of the alarm state, or `None` to never check it.
+    pub fn with_alarm_interval(mut self, alarm_interval: Option<Duration>) -> Self {
+        self.alarm_interval = alarm_interval;
+        self
+    }
+
+    /// Set how to recover once an alarm is found.
+    pub fn with_alarm_policy(mut self, alarm_policy: AlarmPolicy) -> Self {
+        self.alarm_policy = alarm_policy;
+        self
+    }
+}
+
+/// Tracks the polled indexes of a single wait.
+pub(crate) struct Progress<'a> {
+    options: &'a WaitOptions,
+    started: Instant,
+    last_index: Option<u64>,
+    last_change: Instant,
+    last_alarm_check: Option<Instant>,
+}
+
+impl<'a> Progress<'a> {
+    pub(crate) fn new(options: &'a WaitOptions) -> Self {
+        let now = Instant::now();
+        Self {
+            options,
+            started: now,
+            last_index: None,
+            last_change: now,
+            last_alarm_check: None,
+        }
+    }
+
+    /// Record a polled index and find out whether the command at `index` is executed.
+    ///
+    /// The executed index may already be past the command when polled, so
+    /// any index at or after it counts as completion.
+    /// Fails once the wait exceeds its timeout or the executed index stalls.
+    pub(crate) fn update(&mut self, index: u64, current_index: u64) -> DobotResult<bool> {
+        if current_index >= index {
+            return Ok(true);
+        }
+
+        let now = Instant::now();
+        if self.last_index != Some(current_index) {
+            self.last_index = Some(current_index);
+            self.last_change = now;
+        }
+        if let Some(timeout) = self.options.timeout {
+            if now - self.started >= timeout {
+                return Err(DobotError::Timeout);
+            }
+        }
+        if let Some(stall_timeout) = self.options.stall_timeout {
+            if now - self.last_change >= stall_timeout {
+                return Err(DobotError::Stalled {
+                    index: current_index,
+                });
+            }
+        }
+        Ok(false)
+    }
+
+    /// Find out whether the alarm state is to be checked now.
+    ///
+    /// It is always checked before a wait fails because of a limit, since an
+    /// alarm tells more about why the queue stopped.
+    pub(crate) fn alarm_check_due(&mut self, limit_hit: bool) -> bool {
+        let alarm_interval = match self.options.alarm_interval {
+            Some(alarm_interval) => alarm_interval,
+            None => return false,
+        };
+        let now = Instant::now();
+        match self.last_alarm_check {
+            Some(last) if !limit_hit && now - last < alarm_interval => false,
+            _ => {
+                self.last_alarm_check = Some(now);
+                true
+            }
+        }
+    }
+}
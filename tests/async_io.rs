@@ -0,0 +1,38 @@
+#![cfg(all(feature = "async-io", unix))]
+
+use dobot_fx24::{
+    base::CommandID,
+    error::{Error as DobotError, Result as DobotResult},
+    sim::{Fault, FaultPlan, Simulator, VirtualClock},
+    Dobot, WaitOptions,
+};
+use smol::Async;
+use std::{os::unix::net::UnixStream, time::Duration};
+
+#[test]
+fn smol_runtime_test() -> DobotResult<()> {
+    smol::block_on(async {
+        let sim = Simulator::with_clock(VirtualClock::with_step(Duration::from_millis(10)));
+        let (client, server) = Async::<UnixStream>::pair()?;
+        let server_sim = sim.clone();
+        smol::spawn(async move { server_sim.serve(server).await }).detach();
+
+        let mut dobot = Dobot::from_transport(client).await?;
+        dobot.set_wait_options(WaitOptions::new(Duration::from_millis(1)));
+        dobot.move_to(200.0, 100.0, 10.0, 0.0).await?.wait().await?;
+        assert_eq!(dobot.get_pose().await?, sim.pose());
+
+        // timeouts fire without a tokio runtime
+        dobot.set_timeout(Some(Duration::from_millis(50)));
+        sim.set_fault_plan(
+            FaultPlan::new().on_next_command(CommandID::GetPose, Fault::DropResponse),
+        );
+        match dobot.get_pose().await {
+            Err(DobotError::Timeout) => {}
+            other => panic!("expected timeout, got {:?}", other),
+        }
+        assert_eq!(dobot.get_pose().await?, sim.pose());
+
+        Ok(())
+    })
+}
@@ -1,9 +1,9 @@
 #![cfg(feature = "dobot-test")]
 
-use dobot::{error::Result as DobotResult, Dobot};
-use failure::Fallible;
+use dobot_fx24::{error::Result as DobotResult, Dobot};
 use serde::Deserialize;
 use std::{
+    error::Error,
     fs::File,
     io::{prelude::*, BufReader},
     path::PathBuf,
@@ -16,7 +16,7 @@ struct Config {
 }
 
 #[test]
-fn control_dobot_test() -> Fallible<()> {
+fn control_dobot_test() -> Result<(), Box<dyn Error>> {
     let config: Config = {
         let mut reader = BufReader::new(File::open("tests/test_config.toml")?);
         let mut string = String::new();
@@ -26,7 +26,7 @@ fn control_dobot_test() -> Fallible<()> {
 
     let Config { device_file } = config;
 
-    let mut runtime = Runtime::new()?;
+    let runtime = Runtime::new()?;
     runtime.block_on(async {
         let mut dobot = Dobot::open(&device_file).await?;
 
@@ -0,0 +1,280 @@
+#[cfg(feature = "alloc")]
+use dobot_fx24::message::{DobotMessage, MessageDecoder, ParamsWriter};
+#[cfg(feature = "tokio")]
+use dobot_fx24::{codec::DobotCodec, transport};
+use dobot_fx24::{
+    error::{Error as DobotError, Result as DobotResult},
+    message::{CommandID, DobotMessageRef, ParamsReader, MAX_FRAME_LEN},
+};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use dobot_fx24::{Pose, Response};
+#[cfg(feature = "tokio")]
+use futures::{SinkExt, StreamExt};
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncWriteExt;
+#[cfg(feature = "tokio")]
+use tokio_util::codec::Framed;
+
+#[cfg(feature = "alloc")]
+fn pose_request() -> DobotResult<DobotMessage> {
+    DobotMessage::new(CommandID::GetPose, false, false, vec![])
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn decoder_resync_test() -> DobotResult<()> {
+    let frame = pose_request()?.to_bytes();
+    let mut decoder = MessageDecoder::new();
+
+    // garbage, a frame with zero length, then a valid frame split in two
+    decoder.extend(&[0x01, 0xaa, 0x02, 0xaa, 0xaa, 0x00]);
+    decoder.extend(&frame[..4]);
+    assert!(decoder.decode().is_none());
+    decoder.extend(&frame[4..]);
+
+    let msg = decoder.decode().unwrap()?;
+    assert_eq!(msg.id(), CommandID::GetPose);
+    assert_eq!(decoder.bytes_discarded(), 6);
+    assert_eq!(decoder.buffered_len(), 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn decoder_checksum_recovery_test() -> DobotResult<()> {
+    let frame = pose_request()?.to_bytes();
+    let mut corrupted = frame.clone();
+    *corrupted.last_mut().unwrap() ^= 0xff;
+
+    let mut decoder = MessageDecoder::new();
+    decoder.extend(&corrupted);
+    decoder.extend(&frame);
+
+    match decoder.decode() {
+        Some(Err(DobotError::IntegrityError { .. })) => {}
+        other => panic!("expected integrity error, got {:?}", other),
+    }
+    let msg = decoder.decode().unwrap()?;
+    assert_eq!(msg.to_bytes(), frame);
+    assert!(decoder.decode().is_none());
+
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_reader_skips_garbage_test() -> DobotResult<()> {
+    let frame = pose_request()?.to_bytes();
+    let bytes = [&[0x00, 0xaa, 0x13][..], &frame].concat();
+
+    let msg = DobotMessage::from_reader(bytes.as_slice())?;
+    assert_eq!(msg.to_bytes(), frame);
+
+    Ok(())
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn params_too_long_test() {
+    let result = DobotMessage::new(CommandID::SetCpCmd, true, true, vec![0; 254]);
+    assert!(matches!(result, Err(DobotError::ParamsTooLong)));
+    let result = DobotMessage::new(CommandID::SetCpCmd, true, true, vec![0; 253]);
+    assert!(result.is_ok());
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn unknown_command_id_test() -> DobotResult<()> {
+    assert_eq!(CommandID::from(84), CommandID::SetPtpCmd);
+    assert_eq!(u8::from(CommandID::GetQueuedCmdLeftSpace), 247);
+    assert_eq!(CommandID::from(200), CommandID::Unknown(200));
+
+    // frames with IDs the crate does not model still decode
+    let request_msg = DobotMessage::new(CommandID::Unknown(200), true, false, vec![1, 2, 3])?;
+    let decoded = DobotMessage::from_bytes(request_msg.to_bytes())?;
+    assert_eq!(decoded.id(), CommandID::Unknown(200));
+    assert_eq!(decoded.params(), &vec![1, 2, 3]);
+
+    Ok(())
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn params_codec_test() -> DobotResult<()> {
+    let params = ParamsWriter::new()
+        .u8(0x01)
+        .bool(true)
+        .u32(7)
+        .u64(1 << 40)
+        .f32s(&[1.5, -2.0])
+        .bytes(&[0xaa])
+        .finish();
+    assert_eq!(params.len(), 1 + 1 + 4 + 8 + 8 + 1);
+
+    let mut reader = ParamsReader::new(&params);
+    assert_eq!(reader.u8()?, 0x01);
+    assert!(reader.bool()?);
+    assert_eq!(reader.u32()?, 7);
+    assert_eq!(reader.u64()?, 1 << 40);
+    assert_eq!(reader.f32s::<2>()?, [1.5, -2.0]);
+    assert_eq!(reader.remaining(), 1);
+
+    // reading past the end is an error, not a panic
+    match reader.u32() {
+        Err(DobotError::ParamsTruncated {
+            needed: 4,
+            remaining: 1,
+        }) => {}
+        other => panic!("expected ParamsTruncated, got {:?}", other),
+    }
+    match reader.finish() {
+        Err(DobotError::TrailingParams(1)) => {}
+        other => panic!("expected TrailingParams, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+#[test]
+fn response_decode_test() -> DobotResult<()> {
+    // structs of protocol fields decode from exactly their params
+    let pose = ParamsWriter::new()
+        .f32s(&[200.0, 0.0, 10.0, 0.0, 0.0, 45.0, 30.0, 0.0])
+        .finish();
+    assert_eq!(Pose::decode(&pose)?.j2, 45.0);
+
+    // responses of the wrong length fail the same way whatever their type
+    for result in [
+        Pose::decode(&pose[..31]).map(|_| ()),
+        <()>::decode(&[0x00]),
+        u32::decode(&pose[..5]).map(|_| ()),
+    ] {
+        match result {
+            Err(DobotError::UnexpectedResponse { .. }) => {}
+            other => panic!("expected UnexpectedResponse, got {:?}", other),
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn borrowed_message_test() -> DobotResult<()> {
+    // messages are built, encoded and decoded without allocating, as on no_std targets
+    let params = [1, 2, 3, 4];
+    let msg = DobotMessageRef::new(CommandID::SetCpCmd, true, true, &params)?;
+    let mut frame = [0u8; MAX_FRAME_LEN];
+    let len = msg.encode_into(&mut frame)?;
+    assert_eq!(len, msg.encoded_len());
+    assert_eq!(frame[..5], [0xaa, 0xaa, 6, 91, 0x03]);
+    match msg.encode_into(&mut frame[..len - 1]) {
+        Err(DobotError::BufferTooSmall { needed, .. }) => assert_eq!(needed, len),
+        other => panic!("expected BufferTooSmall, got {:?}", other),
+    }
+
+    let decoded = DobotMessageRef::from_bytes(&frame[..len])?;
+    assert_eq!(decoded, msg);
+    assert_eq!(decoded.params(), &params);
+    let mut reader = ParamsReader::new(decoded.params());
+    assert_eq!(reader.u32()?, u32::from_le_bytes(params));
+    reader.finish()?;
+
+    frame[len - 1] ^= 0xff;
+    match DobotMessageRef::from_bytes(&frame[..len]) {
+        Err(DobotError::IntegrityError { .. }) => (),
+        other => panic!("expected IntegrityError, got {:?}", other),
+    }
+    match DobotMessageRef::from_bytes(&frame[..3]) {
+        Err(DobotError::MalformedMessage(_)) => (),
+        other => panic!("expected MalformedMessage, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn borrowed_encoding_test() -> DobotResult<()> {
+    let msg = DobotMessage::new(CommandID::SetCpCmd, true, true, vec![1, 2, 3, 4])?;
+    let mut frame = [0u8; MAX_FRAME_LEN];
+    let len = msg.encode_into(&mut frame)?;
+    assert_eq!(&frame[..len], msg.to_bytes().as_slice());
+    match msg.encode_into(&mut frame[..len - 1]) {
+        Err(DobotError::BufferTooSmall { needed, .. }) => assert_eq!(needed, len),
+        other => panic!("expected BufferTooSmall, got {:?}", other),
+    }
+
+    let borrowed = DobotMessageRef::from_bytes(&frame[..len])?;
+    assert_eq!(borrowed, msg.as_message_ref());
+    assert_eq!(borrowed.params(), &[1, 2, 3, 4]);
+    assert_eq!(borrowed.to_message().to_bytes(), msg.to_bytes());
+
+    // borrowed messages match the owned ones they were built like
+    let params = [1, 2, 3, 4];
+    let built = DobotMessageRef::new(CommandID::SetCpCmd, true, true, &params)?;
+    assert_eq!(built, borrowed);
+
+    // the decoder hands out messages borrowing its buffer
+    let mut decoder = MessageDecoder::new();
+    decoder.extend(&[0x00]);
+    decoder.extend(&frame[..len]);
+    decoder.extend(&pose_request()?.to_bytes());
+    assert_eq!(decoder.decode_ref().unwrap()?.id(), CommandID::SetCpCmd);
+    assert_eq!(decoder.decode_ref().unwrap()?.id(), CommandID::GetPose);
+    assert!(decoder.decode_ref().is_none());
+    assert_eq!(decoder.bytes_discarded(), 1);
+    assert_eq!(decoder.buffered_len(), 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn framed_codec_test() -> DobotResult<()> {
+    let (local, mut remote) = tokio::io::duplex(1024);
+    let mut frames = Framed::new(local, DobotCodec::new());
+
+    // frames come out of garbage and corrupted frames, which end nothing
+    let pose = pose_request()?.to_bytes();
+    let mut corrupted = pose.clone();
+    *corrupted.last_mut().unwrap() ^= 0xff;
+    remote.write_all(&[0x00, 0x13]).await?;
+    remote.write_all(&corrupted).await?;
+    remote.write_all(&pose[..4]).await?;
+    remote.write_all(&pose[4..]).await?;
+    let msg = frames.next().await.unwrap()?;
+    assert_eq!(msg.id(), CommandID::GetPose);
+    assert_eq!(frames.codec().corrupted_frames(), 1);
+    assert_eq!(frames.codec().bytes_discarded(), 2 + corrupted.len() as u64);
+
+    // messages sent through the sink arrive as raw frames
+    let request_msg = DobotMessage::new(CommandID::SetCpCmd, true, true, vec![1, 2, 3])?;
+    frames.send(request_msg.clone()).await?;
+    let received = DobotMessage::from_async_reader(transport::from_tokio(&mut remote)).await?;
+    assert_eq!(received.to_bytes(), request_msg.to_bytes());
+
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn std_error_test() {
+    fn assert_error<E: std::error::Error + Send + Sync + 'static>() {}
+    assert_error::<DobotError>();
+
+    let error = DobotError::from(std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "port closed",
+    ));
+    let source = std::error::Error::source(&error).expect("io errors are chained");
+    assert_eq!(source.to_string(), "port closed");
+    assert!(std::error::Error::source(&DobotError::Timeout).is_none());
+
+    let boxed: Box<dyn std::error::Error + Send + Sync> = Box::new(DobotError::Disconnected);
+    assert_eq!(
+        boxed.to_string(),
+        "the connection to the controller is closed"
+    );
+}
@@ -0,0 +1,858 @@
+#![cfg(feature = "tokio")]
+
+use dobot_fx24::{
+    base::CommandID,
+    command::{self, Command},
+    discovery::DiscoveredDobot,
+    error::{Error as DobotError, Result as DobotResult},
+    message::DobotMessage,
+    sim::{Fault, FaultPlan, Simulator, VirtualClock, ALARM_PLAN_INV_CALC, QUEUE_CAPACITY},
+    AlarmPolicy, CommandQueue, Dobot, Motion, RetryPolicy, Target, WaitOptions,
+};
+use std::time::Duration;
+#[cfg(not(feature = "async-io"))]
+use tokio::time::Instant;
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-3,
+        "{} is not close to {}",
+        actual,
+        expected
+    );
+}
+
+fn simulator() -> Simulator {
+    Simulator::with_clock(VirtualClock::with_step(Duration::from_millis(10)))
+}
+
+async fn connect(sim: &Simulator) -> DobotResult<Dobot> {
+    let mut dobot = Dobot::from_transport(sim.connect()).await?;
+    // the virtual clock only advances with requests, so sleeping between polls is wasted
+    dobot.set_wait_options(WaitOptions::new(Duration::from_millis(1)));
+    Ok(dobot)
+}
+
+#[tokio::test]
+async fn move_in_simulator_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    dobot.move_to(200.0, 100.0, 10.0, 0.0).await?.wait().await?;
+    let pose = dobot.get_pose().await?;
+    assert_close(pose.x, 200.0);
+    assert_close(pose.y, 100.0);
+    assert_close(pose.z, 10.0);
+    assert_eq!(pose, sim.pose());
+
+    dobot.move_by(0.0, -100.0, 0.0, 0.0).await?.wait().await?;
+    let pose = dobot.get_pose().await?;
+    assert_close(pose.y, 0.0);
+
+    dobot
+        .move_joints_to(10.0, 20.0, 30.0, 0.0)
+        .await?
+        .wait()
+        .await?;
+    let pose = dobot.get_pose().await?;
+    assert_eq!(
+        (pose.j1, pose.j2, pose.j3, pose.j4),
+        (10.0, 20.0, 30.0, 0.0)
+    );
+
+    let result = dobot
+        .move_to_target(
+            Target::RelativeJoints {
+                j1: 1.0,
+                j2: 0.0,
+                j3: 0.0,
+                j4: 0.0,
+            },
+            Motion::Jump,
+        )
+        .await;
+    assert!(result.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn end_effector_in_simulator_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    dobot.grip().await?.wait().await?;
+    assert_eq!(sim.end_effector().gripper, (true, true));
+    dobot.release().await?.wait().await?;
+    assert_eq!(sim.end_effector().gripper, (true, false));
+
+    dobot
+        .set_end_effector_suction_cup(true)
+        .await?
+        .wait()
+        .await?;
+    assert_eq!(sim.end_effector().suction_cup, (true, true));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn queue_execution_in_simulator_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    dobot.set_queued_cmd_stop_exec().await?;
+    let pending = dobot.move_to(220.0, 0.0, 0.0, 0.0).await?.detach();
+    assert_eq!(sim.queued_cmd_len(), 1);
+    assert!(dobot.get_queued_cmd_current_index().await? < pending.index);
+
+    dobot.set_queued_cmd_start_exec().await?;
+    dobot.wait_for(&pending).await?;
+    assert_close(sim.pose().x, 220.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn full_queue_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    dobot.set_queued_cmd_stop_exec().await?;
+    for _ in 0..QUEUE_CAPACITY {
+        dobot.move_to(220.0, 0.0, 0.0, 0.0).await?;
+    }
+    let last_index = dobot.last_queued_index();
+
+    // the rejection is answered rather than left to time out
+    match dobot.move_to(200.0, 0.0, 0.0, 0.0).await {
+        Err(DobotError::UnexpectedResponse { .. }) => {}
+        other => panic!("expected UnexpectedResponse, got {:?}", other.map(|_| ())),
+    }
+    assert_eq!(sim.queued_cmd_len(), QUEUE_CAPACITY);
+    assert_eq!(dobot.last_queued_index(), last_index);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn motion_timing_in_simulator_test() -> DobotResult<()> {
+    let clock = VirtualClock::new();
+    let sim = Simulator::with_clock(clock.clone());
+    let mut dobot = connect(&sim).await?;
+
+    // 100 mm at 200 mm/s and 200 mm/s^2 never reaches full speed: 2 * sqrt(100 / 200) s
+    let index = dobot
+        .move_to(200.0, 100.0, 0.0, 0.0)
+        .await?
+        .get_queued_index();
+    assert!(sim.is_moving());
+
+    clock.advance(Duration::from_millis(707));
+    assert!(dobot.get_queued_cmd_current_index().await? < index);
+    let pose = dobot.get_pose().await?;
+    assert!((pose.y - 50.0).abs() < 1.0, "y = {}", pose.y);
+
+    clock.advance(Duration::from_millis(708));
+    assert_eq!(dobot.get_queued_cmd_current_index().await?, index);
+    assert_close(dobot.get_pose().await?.y, 100.0);
+    assert!(!sim.is_moving());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn extreme_motion_params_test() -> DobotResult<()> {
+    let clock = VirtualClock::new();
+    let sim = Simulator::with_clock(clock.clone());
+    let mut dobot = connect(&sim).await?;
+
+    // ratios this large overflow the scaled velocity to infinity
+    dobot.set_ptp_common_params(f32::MAX, f32::MAX).await?;
+    let index = dobot
+        .move_to(200.0, 100.0, 0.0, 0.0)
+        .await?
+        .get_queued_index();
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(dobot.get_queued_cmd_current_index().await?, index);
+
+    // non-positive rates are rejected and leave the previous parameters in place
+    dobot.set_ptp_common_params(0.0, -1.0).await?;
+    let index = dobot
+        .move_to(200.0, -100.0, 0.0, 0.0)
+        .await?
+        .get_queued_index();
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(dobot.get_queued_cmd_current_index().await?, index);
+    assert_close(dobot.get_pose().await?.y, -100.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fault_injection_in_simulator_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    sim.set_fault_plan(
+        FaultPlan::new()
+            .on_next_command(CommandID::GetPose, Fault::CorruptChecksum)
+            .at_request(1, Fault::Alarm(0x40))
+            .at_request(2, Fault::StuckQueueIndex)
+            .at_request(4, Fault::Disconnect),
+    );
+
+    match dobot.get_pose().await {
+        Err(DobotError::IntegrityError { .. }) => {}
+        other => panic!("expected integrity error, got {:?}", other.map(|_| ())),
+    }
+    assert!(dobot.get_pose().await.is_ok());
+    assert_ne!(sim.alarms(), [0; 16]);
+
+    let index = dobot.get_queued_cmd_current_index().await?;
+    assert_eq!(dobot.get_queued_cmd_current_index().await?, index);
+
+    match dobot.get_pose().await {
+        Err(DobotError::IoError(_)) => {}
+        other => panic!("expected IO error, got {:?}", other.map(|_| ())),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn corrupt_checksum_fault_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    sim.set_fault_plan(
+        FaultPlan::new().on_next_command(CommandID::GetPose, Fault::CorruptChecksum),
+    );
+    match dobot.get_pose().await {
+        Err(DobotError::IntegrityError { received, expected }) => {
+            assert_eq!(received, expected.wrapping_add(1))
+        }
+        other => panic!("expected IntegrityError, got {:?}", other.map(|_| ())),
+    }
+
+    // only the corrupt frame is discarded, so the next reply decodes cleanly
+    assert_eq!(dobot.get_pose().await?, sim.pose());
+    assert_eq!(dobot.bytes_discarded(), 38);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn drop_bytes_fault_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+    dobot.set_timeout(Some(Duration::from_millis(100)));
+
+    // without its header the reply is skipped as garbage
+    sim.set_fault_plan(FaultPlan::new().on_next_command(
+        CommandID::GetPose,
+        Fault::DropBytes {
+            offset: 0,
+            count: 2,
+        },
+    ));
+    match dobot.get_pose().await {
+        Err(DobotError::Timeout) => {}
+        other => panic!("expected Timeout, got {:?}", other.map(|_| ())),
+    }
+    assert_eq!(dobot.get_pose().await?, sim.pose());
+    assert!(dobot.bytes_discarded() >= 36);
+
+    // a reply shorter than its length byte claims must not swallow the next one
+    sim.set_fault_plan(FaultPlan::new().on_next_command(
+        CommandID::GetPose,
+        Fault::DropBytes {
+            offset: 5,
+            count: 4,
+        },
+    ));
+    match dobot.get_pose().await {
+        Err(DobotError::Timeout) => {}
+        other => panic!("expected Timeout, got {:?}", other.map(|_| ())),
+    }
+    assert_eq!(dobot.get_pose().await?, sim.pose());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn drop_response_fault_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+    dobot.set_timeout(Some(Duration::from_millis(100)));
+
+    sim.set_fault_plan(FaultPlan::new().on_next_command(CommandID::GetPose, Fault::DropResponse));
+    match dobot.get_pose().await {
+        Err(DobotError::Timeout) => {}
+        other => panic!("expected Timeout, got {:?}", other.map(|_| ())),
+    }
+    assert_eq!(dobot.get_pose().await?, sim.pose());
+    assert_eq!(dobot.bytes_discarded(), 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn unexpected_response_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    // a GetPose response frame carries 6 bytes of framing and 32 bytes of params
+    sim.set_fault_plan(FaultPlan::new().on_next_command(
+        CommandID::GetPose,
+        Fault::DuplicateBytes {
+            offset: 0,
+            count: 38,
+        },
+    ));
+    dobot.get_pose().await?;
+    match dobot.get_queued_cmd_current_index().await {
+        Err(DobotError::UnexpectedResponse { .. }) => {}
+        other => panic!("expected unexpected response, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn timeout_and_retry_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+    dobot.set_timeout(Some(Duration::from_millis(50)));
+
+    sim.set_fault_plan(FaultPlan::new().on_next_command(CommandID::GetPose, Fault::DropResponse));
+    match dobot.get_pose().await {
+        Err(DobotError::Timeout) => {}
+        other => panic!("expected timeout, got {:?}", other),
+    }
+
+    dobot.set_retry_policy(RetryPolicy::new(1));
+    sim.set_fault_plan(FaultPlan::new().on_next_command(CommandID::GetPose, Fault::DropResponse));
+    dobot.get_pose().await?;
+
+    // queued commands are not retried, but reconciled through the executed index
+    let last_index = dobot.last_queued_index().unwrap();
+    sim.set_fault_plan(FaultPlan::new().on_next_command(CommandID::SetPtpCmd, Fault::DropResponse));
+    match dobot.move_to(210.0, 0.0, 0.0, 0.0).await {
+        Err(DobotError::Timeout) => {}
+        other => panic!("expected timeout, got {:?}", other.map(|_| ())),
+    }
+    let index = loop {
+        if let Some(index) = dobot.reconcile_queued().await? {
+            break index;
+        }
+    };
+    assert_eq!(index, last_index + 1);
+    assert_close(sim.pose().x, 210.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn late_reply_test() -> DobotResult<()> {
+    let clock = VirtualClock::new();
+    let sim = Simulator::with_clock(clock.clone());
+    let mut dobot = connect(&sim).await?;
+    dobot.set_timeout(Some(Duration::from_millis(100)));
+
+    // the reply starts arriving, but finishes only after the next request gave up waiting for it
+    sim.set_fault_plan(FaultPlan::new().on_next_command(
+        CommandID::GetDeviceTime,
+        Fault::Stall {
+            offset: 5,
+            delay: Duration::from_millis(250),
+        },
+    ));
+    match dobot.execute(&GetDeviceTime).await {
+        Err(DobotError::Timeout) => {}
+        other => panic!("expected timeout, got {:?}", other),
+    }
+
+    clock.advance(Duration::from_secs(1));
+    let time = dobot.execute(&GetDeviceTime).await?;
+    assert_eq!(time as u128, clock.elapsed().as_millis());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn reconcile_lost_commands_test() -> DobotResult<()> {
+    let clock = VirtualClock::new();
+    let sim = Simulator::with_clock(clock.clone());
+    let mut dobot = connect(&sim).await?;
+    dobot.set_timeout(Some(Duration::from_millis(50)));
+
+    let last_index = dobot.last_queued_index().unwrap();
+    sim.set_fault_plan(
+        FaultPlan::new()
+            .at_request(0, Fault::DropResponse)
+            .at_request(1, Fault::DropResponse),
+    );
+    for y in [100.0, -100.0] {
+        match dobot.move_to(200.0, y, 0.0, 0.0).await {
+            Err(DobotError::Timeout) => {}
+            other => panic!("expected timeout, got {:?}", other.map(|_| ())),
+        }
+    }
+    assert_eq!(dobot.reconcile_queued().await?, None);
+
+    // both lost commands are accounted for once executed
+    clock.advance(Duration::from_secs(60));
+    assert_eq!(dobot.reconcile_queued().await?, Some(last_index + 2));
+    assert_eq!(dobot.last_queued_index(), Some(last_index + 2));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn retry_on_integrity_error_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+    dobot.set_retry_policy(RetryPolicy::new(3));
+
+    // an idempotent read is sent again after a corrupt reply
+    sim.set_fault_plan(
+        FaultPlan::new().on_next_command(CommandID::GetPose, Fault::CorruptChecksum),
+    );
+    assert_eq!(dobot.get_pose().await?, sim.pose());
+    assert_eq!(dobot.bytes_discarded(), 38);
+
+    // a queued write is not, since it already reached the queue
+    dobot.set_queued_cmd_stop_exec().await?;
+    sim.set_fault_plan(
+        FaultPlan::new().on_next_command(CommandID::SetPtpCmd, Fault::CorruptChecksum),
+    );
+    match dobot.move_to(200.0, 100.0, 0.0, 0.0).await {
+        Err(DobotError::IntegrityError { .. }) => {}
+        other => panic!("expected IntegrityError, got {:?}", other.map(|_| ())),
+    }
+    assert_eq!(sim.queued_cmd_len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cancelled_request_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    sim.set_fault_plan(
+        FaultPlan::new()
+            .on_next_command(CommandID::GetPose, Fault::Delay(Duration::from_millis(50))),
+    );
+    let cancelled = tokio::time::timeout(Duration::from_millis(10), dobot.get_pose()).await;
+    assert!(cancelled.is_err());
+
+    // the late pose reply must not be taken for the index reply
+    let index = dobot.get_queued_cmd_current_index().await?;
+    assert_eq!(index, sim.queued_cmd_current_index());
+    dobot.get_pose().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn shared_handle_test() -> DobotResult<()> {
+    let sim = simulator();
+    let handle = connect(&sim).await?.into_handle();
+
+    let command = handle.move_to(200.0, 100.0, 0.0, 0.0).await?;
+    let waiter = {
+        let handle = handle.clone();
+        tokio::spawn(async move { handle.wait_for(&command).await })
+    };
+
+    // the pose can be polled by other tasks while the move is waited for
+    let mut poses = 0;
+    while !waiter.is_finished() {
+        handle.get_pose().await?;
+        poses += 1;
+    }
+    waiter.await.unwrap()?;
+    assert!(poses > 0);
+    assert_close(handle.get_pose().await?.y, 100.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn detached_tickets_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    // enqueue a whole path before waiting on any of it
+    let mut tickets = vec![];
+    for (index, &(x, y)) in [(200.0, 50.0), (200.0, -50.0), (150.0, -50.0), (150.0, 50.0)]
+        .iter()
+        .enumerate()
+    {
+        let ticket = dobot
+            .move_to(x, y, 0.0, 0.0)
+            .await?
+            .detach_labeled(format!("corner {}", index));
+        tickets.push(ticket);
+    }
+    assert_eq!(tickets[3].label.as_deref(), Some("corner 3"));
+    assert!(tickets
+        .windows(2)
+        .all(|pair| pair[1].index == pair[0].index + 1));
+
+    dobot.wait_for(&tickets[1]).await?;
+    assert!(sim.queued_cmd_current_index() >= tickets[1].index);
+
+    dobot.wait_all(&tickets).await?;
+    let pose = dobot.get_pose().await?;
+    assert_close(pose.x, 150.0);
+    assert_close(pose.y, 50.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn completion_semantics_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    let first = dobot.move_to(200.0, 50.0, 0.0, 0.0).await?.detach();
+    dobot.move_to(200.0, -50.0, 0.0, 0.0).await?;
+    dobot.wait_until_idle().await?;
+    assert_eq!(sim.queued_cmd_current_index(), first.index + 1);
+
+    // the executed index is already past the first command
+    dobot.wait_for(&first).await?;
+
+    // indexes restart after a clear, so older tickets are rejected
+    let pending = dobot.move_to(150.0, 0.0, 0.0, 0.0).await?.detach();
+    dobot.set_queued_cmd_clear().await?;
+    match dobot.wait_for(&pending).await {
+        Err(DobotError::QueueCleared) => {}
+        other => panic!("expected QueueCleared, got {:?}", other),
+    }
+    assert_eq!(dobot.last_queued_index(), None);
+    dobot.wait_until_idle().await?;
+
+    Ok(())
+}
+
+// paused time only reaches the waits when tokio provides the timers
+#[cfg(not(feature = "async-io"))]
+#[tokio::test(start_paused = true)]
+async fn wait_options_test() -> DobotResult<()> {
+    // the arm only moves when the clock is advanced
+    let clock = VirtualClock::new();
+    let sim = Simulator::with_clock(clock.clone());
+    let mut dobot = connect(&sim).await?;
+    let poll_interval = Duration::from_millis(10);
+
+    let options = WaitOptions::new(poll_interval).with_timeout(Duration::from_secs(1));
+    let started = Instant::now();
+    match dobot
+        .move_to(200.0, 100.0, 0.0, 0.0)
+        .await?
+        .wait_with(options)
+        .await
+    {
+        Err(DobotError::Timeout) => {}
+        other => panic!("expected Timeout, got {:?}", other),
+    }
+    let waited = started.elapsed();
+    assert!(waited >= Duration::from_secs(1), "waited {:?}", waited);
+    assert!(
+        waited < Duration::from_secs(1) + poll_interval * 2,
+        "waited {:?}",
+        waited
+    );
+    clock.advance(Duration::from_secs(10));
+    dobot.wait_until_idle().await?;
+
+    // a jammed queue is reported instead of waited for forever
+    sim.set_fault_plan(
+        FaultPlan::new().on_next_command(CommandID::SetPtpCmd, Fault::StuckQueueIndex),
+    );
+    let stuck = dobot.move_to(200.0, -100.0, 0.0, 0.0).await?.detach();
+    clock.advance(Duration::from_secs(10));
+    let options = WaitOptions::new(poll_interval)
+        .with_timeout(Duration::from_secs(5))
+        .with_stall_timeout(Duration::from_millis(500));
+    let started = Instant::now();
+    match dobot.wait_for_with(&stuck, &options).await {
+        Err(DobotError::Stalled { index }) => assert_eq!(index, stuck.index - 1),
+        other => panic!("expected Stalled, got {:?}", other),
+    }
+    let waited = started.elapsed();
+    assert!(waited >= Duration::from_millis(500), "waited {:?}", waited);
+    assert!(
+        waited < Duration::from_millis(500) + poll_interval * 2,
+        "waited {:?}",
+        waited
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn alarm_aware_wait_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    // an unreachable target halts the queue instead of hanging the wait
+    let handle = dobot.move_to(400.0, 0.0, 0.0, 0.0).await?;
+    let error = handle.wait().await.unwrap_err();
+    match &error {
+        DobotError::OutOfWorkspace(alarms) => {
+            assert!(alarms.contains(ALARM_PLAN_INV_CALC));
+            assert!(alarms.to_string().contains("inverse kinematics"));
+        }
+        other => panic!("expected OutOfWorkspace, got {:?}", other),
+    }
+    assert!(error.alarms().unwrap().is_out_of_workspace());
+    assert_ne!(sim.alarms(), [0; 16]);
+    dobot.clear_all_alarms_state().await?;
+    assert!(dobot.get_alarms_state().await?.is_empty());
+
+    // the policy can recover the controller before the error is returned
+    let pending = dobot.move_to(200.0, 100.0, 0.0, 0.0).await?.detach();
+    dobot.move_to(200.0, -100.0, 0.0, 0.0).await?;
+    sim.raise_alarm(0x40)?;
+    let options = WaitOptions::new(Duration::from_millis(1))
+        .with_alarm_policy(AlarmPolicy::ClearAlarmsAndQueue);
+    match dobot.wait_for_with(&pending, &options).await {
+        Err(DobotError::Alarm(alarms)) => assert_eq!(alarms.codes(), vec![0x40]),
+        other => panic!("expected Alarm, got {:?}", other),
+    }
+    assert_eq!(sim.alarms(), [0; 16]);
+    assert_eq!(sim.queued_cmd_len(), 0);
+    assert_eq!(dobot.last_queued_index(), None);
+
+    Ok(())
+}
+
+#[test]
+fn out_of_range_alarm_test() {
+    let sim = simulator();
+    match sim.raise_alarm(200) {
+        Err(DobotError::InvalidParameter(_)) => {}
+        other => panic!("expected InvalidParameter, got {:?}", other),
+    }
+    assert_eq!(sim.alarms(), [0; 16]);
+}
+
+#[test]
+#[should_panic(expected = "alarm code 200 is out of range")]
+fn out_of_range_alarm_fault_test() {
+    FaultPlan::new().at_request(0, Fault::Alarm(200));
+}
+
+#[tokio::test]
+async fn command_queue_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    // far more CP points than the firmware queue holds
+    let mut queue = CommandQueue::new();
+    for step in 0..100 {
+        queue.push_command(&command::SetCpCmd {
+            x: 200.0,
+            y: step as f32,
+            z: 0.0,
+        })?;
+    }
+    assert!(queue
+        .push(DobotMessage::new(CommandID::GetPose, false, false, vec![])?)
+        .is_err());
+    assert_eq!(queue.pending_len(), 100);
+
+    let mut reports = vec![];
+    queue
+        .run_with_progress(&mut dobot, |progress| reports.push(progress))
+        .await?;
+
+    assert!(reports.len() > 1);
+    assert!(reports.iter().all(|progress| progress.submitted == 100));
+    // the firmware queue plus the command in motion
+    assert!(reports
+        .iter()
+        .all(|progress| progress.sent - progress.executed <= QUEUE_CAPACITY + 1));
+    assert!(queue.progress().is_done());
+    assert_eq!(queue.pending_len(), 0);
+    assert_close(dobot.get_pose().await?.y, 99.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn command_queue_recovery_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+    dobot.set_queued_cmd_stop_exec().await?;
+
+    let mut queue = CommandQueue::new();
+    for step in 0..3 {
+        queue.push_command(&command::SetCpCmd {
+            x: 200.0,
+            y: step as f32,
+            z: 0.0,
+        })?;
+    }
+
+    // a failed send keeps the command pending
+    sim.set_fault_plan(
+        FaultPlan::new().on_next_command(CommandID::SetCpCmd, Fault::CorruptChecksum),
+    );
+    match queue.feed(&mut dobot).await {
+        Err(DobotError::IntegrityError { .. }) => {}
+        other => panic!("expected IntegrityError, got {:?}", other),
+    }
+    assert_eq!(queue.pending_len(), 3);
+    assert_eq!(queue.progress().sent, 0);
+    assert_eq!(queue.feed(&mut dobot).await?, 3);
+    assert_eq!(queue.progress().sent, 3);
+
+    // commands sent before a clear are dropped and the queue keeps working
+    sim.clear_faults();
+    dobot.set_queued_cmd_clear().await?;
+    dobot.set_queued_cmd_start_exec().await?;
+    for step in 0..3 {
+        queue.push_command(&command::SetCpCmd {
+            x: 200.0,
+            y: -step as f32,
+            z: 0.0,
+        })?;
+    }
+    queue.run(&mut dobot).await?;
+    let progress = queue.progress();
+    assert_eq!(progress.cleared, 3);
+    assert_eq!(progress.executed, 3);
+    assert!(progress.is_done());
+    assert_close(dobot.get_pose().await?.y, -2.0);
+
+    Ok(())
+}
+
+/// A command defined outside the crate.
+struct GetDeviceTime;
+
+impl Command for GetDeviceTime {
+    const ID: CommandID = CommandID::GetDeviceTime;
+    const RW: bool = false;
+    const IS_QUEUED: bool = false;
+    type Response = u32;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+/// The same command with a mismatched response type.
+struct GetDeviceTimeAsIndex;
+
+impl Command for GetDeviceTimeAsIndex {
+    const ID: CommandID = CommandID::GetDeviceTime;
+    const RW: bool = false;
+    const IS_QUEUED: bool = false;
+    type Response = u64;
+
+    fn encode(&self) -> Vec<u8> {
+        vec![]
+    }
+}
+
+#[tokio::test]
+async fn device_probe_test() -> DobotResult<()> {
+    let sim = simulator();
+
+    // probing only reads the identity and leaves the controller as it is
+    sim.set_fault_plan(
+        FaultPlan::new()
+            .on_every_command(CommandID::SetQueuedCmdStartExec, Fault::Disconnect)
+            .on_every_command(CommandID::SetQueuedCmdClear, Fault::Disconnect),
+    );
+    let found = DiscoveredDobot::probe("/dev/ttyUSB0", sim.connect()).await?;
+    assert_eq!(
+        found,
+        DiscoveredDobot {
+            path: "/dev/ttyUSB0".to_string(),
+            serial: "DOBOT-SIM-0001".to_string(),
+            name: "Dobot Magician".to_string(),
+        }
+    );
+    sim.clear_faults();
+
+    let mut dobot = connect(&sim).await?;
+    assert_eq!(dobot.get_device_sn().await?, found.serial);
+    assert_eq!(dobot.get_device_name().await?, found.name);
+
+    // a silent device fails the probe instead of hanging discovery
+    sim.set_fault_plan(
+        FaultPlan::new().on_next_command(CommandID::GetSetDeviceSn, Fault::DropResponse),
+    );
+    match DiscoveredDobot::probe("/dev/ttyUSB1", sim.connect()).await {
+        Err(DobotError::Timeout) => {}
+        other => panic!("expected Timeout, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn typed_command_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = connect(&sim).await?;
+
+    let index = dobot
+        .execute(&command::SetPtpCmd {
+            mode: dobot_fx24::Mode::MODE_PTP_MOVL_XYZ,
+            x: 200.0,
+            y: 50.0,
+            z: 0.0,
+            r: 0.0,
+        })
+        .await?;
+    assert_eq!(dobot.last_queued_index(), Some(index));
+    dobot.wait_until_idle().await?;
+    assert_eq!(dobot.execute(&command::GetPose).await?, sim.pose());
+
+    let first = dobot.execute(&GetDeviceTime).await?;
+    let second = dobot.execute(&GetDeviceTime).await?;
+    assert!(second > first);
+
+    match dobot.execute(&GetDeviceTimeAsIndex).await {
+        Err(DobotError::UnexpectedResponse { expected, got }) => {
+            assert_eq!(expected, "at least 8 bytes of params");
+            assert_eq!(got, "4 bytes");
+        }
+        other => panic!("expected UnexpectedResponse, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn blocking_api_test() -> DobotResult<()> {
+    let sim = simulator();
+    let mut dobot = dobot_fx24::blocking::Dobot::from_transport_with(|| sim.connect())?;
+    dobot.set_wait_options(WaitOptions::new(Duration::from_millis(1)));
+
+    dobot.move_to(200.0, 100.0, 10.0, 0.0)?.wait()?;
+    assert_eq!(dobot.get_pose()?, sim.pose());
+    assert_close(sim.pose().y, 100.0);
+
+    let first = dobot.move_by(0.0, -50.0, 0.0, 0.0)?.detach();
+    let second = dobot.move_by(0.0, -50.0, 0.0, 0.0)?.detach();
+    dobot.wait_all(&[first, second])?;
+    assert_close(dobot.get_pose()?.y, 0.0);
+    assert_eq!(
+        dobot.get_queued_cmd_current_index()?,
+        dobot.last_queued_index().unwrap()
+    );
+
+    dobot.grip()?.wait()?;
+    assert!(dobot.get_alarms_state()?.is_empty());
+
+    Ok(())
+}